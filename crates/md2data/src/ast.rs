@@ -1,5 +1,8 @@
-use serde::{Serialize, Serializer};
+use std::fmt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::{MapAccess, SeqAccess, Visitor};
 use serde::ser::SerializeMap;
+use crate::ConvertError;
 
 #[derive(Debug, Serialize)]
 #[serde(tag = "type")]
@@ -24,15 +27,88 @@ pub enum NodeOrString {
 /// Features:
 /// - Preserves key order
 /// - Differentiates between Leaf (String), List (Array), and Branch (Map)
+/// - Typed leaves (Bool, Integer, Float, Null) come from embedded data, type hints,
+///   CSV cell inference, ordered lists as objects and empty sections set to null
 #[derive(Debug, Clone, PartialEq)]
 pub enum MinifiedNode {
     String(String),
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Null,
     Array(Vec<MinifiedNode>),
     // We use Vec<(Key, Value)> instead of HashMap to strictly preserve 
     // the order of keys as they appear in the Markdown.
     Map(Vec<(String, MinifiedNode)>),
 }
 
+impl MinifiedNode {
+    /// Finds the subtree at `path`
+    ///
+    /// `path` is a heading path like `Services/API/Port`, or a JSON Pointer like
+    /// `/Services/API/Port` where `~1` stands for `/` and `~0` for `~` in keys.
+    /// Numeric segments index into arrays, and an empty path selects everything.
+    pub fn select(&self, path: &str) -> Result<&MinifiedNode, ConvertError> {
+        let segments = path_segments(path);
+
+        let mut current = self;
+        for (i, segment) in segments.iter().enumerate() {
+            let next = match current {
+                MinifiedNode::Map(entries) => entries.iter().find(|(k, _)| k == segment).map(|(_, v)| v),
+                MinifiedNode::Array(items) => segment.parse::<usize>().ok().and_then(|idx| items.get(idx)),
+                _ => None,
+            };
+            current = next.ok_or_else(|| ConvertError::Select {
+                path: path.to_string(),
+                parent: match i {
+                    0 => "the document".to_string(),
+                    _ => format!("\"{}\"", segments[..i].join("/")),
+                },
+                segment: segment.clone(),
+            })?;
+        }
+        Ok(current)
+    }
+
+    /// Sorts every map by key, recursively. Sorting is stable, so duplicate keys keep their order
+    pub(crate) fn sort_keys(&mut self) {
+        match self {
+            MinifiedNode::Map(entries) => {
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                entries.iter_mut().for_each(|(_, value)| value.sort_keys());
+            }
+            MinifiedNode::Array(items) => items.iter_mut().for_each(MinifiedNode::sort_keys),
+            _ => {}
+        }
+    }
+
+    /// Removes nulls from every map and array, recursively
+    pub(crate) fn drop_nulls(&mut self) {
+        match self {
+            MinifiedNode::Map(entries) => {
+                entries.retain(|(_, value)| *value != MinifiedNode::Null);
+                entries.iter_mut().for_each(|(_, value)| value.drop_nulls());
+            }
+            MinifiedNode::Array(items) => {
+                items.retain(|item| *item != MinifiedNode::Null);
+                items.iter_mut().for_each(MinifiedNode::drop_nulls);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Splits a heading path or JSON Pointer into keys
+pub(crate) fn path_segments(path: &str) -> Vec<String> {
+    match path.strip_prefix('/') {
+        Some(pointer) => pointer.split('/')
+            .map(|s| s.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+        None if path.is_empty() => vec![],
+        None => path.split('/').map(|s| s.trim().to_string()).collect(),
+    }
+}
+
 // Custom serializer to make the Map variant output as a JSON object, not an array of tuples
 impl Serialize for MinifiedNode {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -41,6 +117,10 @@ impl Serialize for MinifiedNode {
     {
         match self {
             MinifiedNode::String(s) => serializer.serialize_str(s),
+            MinifiedNode::Bool(b) => serializer.serialize_bool(*b),
+            MinifiedNode::Integer(i) => serializer.serialize_i64(*i),
+            MinifiedNode::Float(f) => serializer.serialize_f64(*f),
+            MinifiedNode::Null => serializer.serialize_none(),
             MinifiedNode::Array(arr) => arr.serialize(serializer),
             MinifiedNode::Map(kvs) => {
                 let mut map = serializer.serialize_map(Some(kvs.len()))?;
@@ -52,3 +132,95 @@ impl Serialize for MinifiedNode {
         }
     }
 }
+
+// Custom deserializer so embedded JSON/YAML/TOML keeps its key order
+impl<'de> Deserialize<'de> for MinifiedNode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(MinifiedNodeVisitor)
+    }
+}
+
+struct MinifiedNodeVisitor;
+
+// The toml crate hands datetimes to deserialize_any as a map with this single key
+const TOML_DATETIME_KEY: &str = "$__toml_private_datetime";
+
+impl<'de> Visitor<'de> for MinifiedNodeVisitor {
+    type Value = MinifiedNode;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string, number, boolean, null, sequence or map")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(MinifiedNode::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(MinifiedNode::Integer(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(match i64::try_from(v) {
+            Ok(i) => MinifiedNode::Integer(i),
+            Err(_) => MinifiedNode::Float(v as f64),
+        })
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(MinifiedNode::Float(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(MinifiedNode::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
+        Ok(MinifiedNode::String(v))
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E> {
+        Ok(MinifiedNode::Null)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(MinifiedNode::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        MinifiedNode::deserialize(deserializer)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut items = vec![];
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(MinifiedNode::Array(items))
+    }
+
+    fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut map = vec![];
+        while let Some((key, value)) = access.next_entry::<String, MinifiedNode>()? {
+            map.push((key, value));
+        }
+
+        if let [(key, value)] = map.as_slice()
+            && key == TOML_DATETIME_KEY {
+            return Ok(value.clone());
+        }
+        Ok(MinifiedNode::Map(map))
+    }
+}
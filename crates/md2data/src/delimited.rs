@@ -0,0 +1,118 @@
+use crate::ast::MinifiedNode;
+
+/// Options for turning ```csv and ```tsv blocks into records
+#[derive(Debug, Clone, Default)]
+pub struct CsvOptions {
+    /// Field separator, defaults to `,` for csv and a tab for tsv
+    pub delimiter: Option<char>,
+    /// Turn `42`, `1.5`, `true`/`false` and empty fields into typed values
+    pub infer_types: bool,
+}
+
+/// Parses delimited text into an array of maps keyed by the header row
+///
+/// Fields may be quoted with `"`, and `""` inside quotes is a literal quote.
+pub(crate) fn parse_records(text: &str, delimiter: char, infer_types: bool) -> Result<MinifiedNode, String> {
+    let mut rows = split_rows(text, delimiter)?.into_iter();
+
+    let header = match rows.next() {
+        Some(header) => header,
+        None => return Ok(MinifiedNode::Array(vec![])),
+    };
+
+    let mut records = vec![];
+    for (i, row) in rows.enumerate() {
+        if row.len() != header.len() {
+            // +2: one for the header, one for 1-based numbering
+            return Err(format!("row {} has {} fields, expected {}", i + 2, row.len(), header.len()));
+        }
+
+        let entries = header.iter()
+            .zip(row)
+            .map(|(key, field)| {
+                let value = if infer_types { infer(field) } else { MinifiedNode::String(field) };
+                (key.clone(), value)
+            })
+            .collect();
+        records.push(MinifiedNode::Map(entries));
+    }
+
+    Ok(MinifiedNode::Array(records))
+}
+
+fn split_rows(text: &str, delimiter: char) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = vec![];
+    let mut row: Vec<String> = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                // Skip blank lines
+                if !(row.len() == 1 && row[0].is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            c if c == delimiter => row.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(format!("unterminated quoted field in row {}", rows.len() + 1));
+    }
+
+    row.push(field);
+    if !(row.len() == 1 && row[0].is_empty()) {
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Guesses the type of a single field
+/// Numbers with leading zeros ("0755") stay strings
+fn infer(field: String) -> MinifiedNode {
+    let trimmed = field.trim();
+
+    if trimmed.is_empty() {
+        return MinifiedNode::Null;
+    }
+    match trimmed {
+        "true" => return MinifiedNode::Bool(true),
+        "false" => return MinifiedNode::Bool(false),
+        _ => {}
+    }
+
+    let digits = trimmed.strip_prefix('-').unwrap_or(trimmed);
+    let leading_zero = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+    if !leading_zero {
+        if let Ok(i) = trimmed.parse::<i64>() {
+            return MinifiedNode::Integer(i);
+        }
+        if trimmed.contains(|c: char| c.is_ascii_digit())
+            && let Ok(f) = trimmed.parse::<f64>()
+            && f.is_finite() {
+            return MinifiedNode::Float(f);
+        }
+    }
+
+    MinifiedNode::String(field)
+}
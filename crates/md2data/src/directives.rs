@@ -0,0 +1,232 @@
+use std::fmt;
+use std::ops::Range;
+use pulldown_cmark::{Event, Tag, TagEnd};
+use crate::ast::MinifiedNode;
+
+/// An in-document instruction written as an HTML comment
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Directive {
+    /// `<!-- md2data:ignore -->`
+    Ignore,
+    /// `<!-- md2data:ignore-end -->`
+    IgnoreEnd,
+    /// `<!-- md2data:key name -->`
+    Key(String),
+    /// `<!-- type: int -->`, placed after a heading
+    Type(TypeHint),
+    /// `<!-- md2data:include path/to/other.md -->`
+    Include(String),
+}
+
+/// An explicit type for a section's value
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TypeHint {
+    String,
+    Int,
+    Float,
+    Bool,
+    /// `list` or `list<T>`, a single value becomes a one-item list
+    List(Option<Box<TypeHint>>),
+}
+
+/// Recognises a `<!-- md2data:... -->` or `<!-- type: ... -->` comment
+pub(crate) fn parse_directive(html: &str) -> Option<Directive> {
+    let body = html.trim()
+        .strip_prefix("<!--")?
+        .strip_suffix("-->")?
+        .trim();
+
+    if let Some(hint) = body.strip_prefix("type:") {
+        return TypeHint::parse(hint.trim()).map(Directive::Type);
+    }
+    let body = body.strip_prefix("md2data:")?;
+
+    let (name, arg) = match body.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, arg.trim()),
+        None => (body, ""),
+    };
+
+    match name {
+        "ignore" => Some(Directive::Ignore),
+        "ignore-end" => Some(Directive::IgnoreEnd),
+        "key" if !arg.is_empty() => Some(Directive::Key(arg.to_string())),
+        "include" if !arg.is_empty() => Some(Directive::Include(arg.to_string())),
+        _ => None,
+    }
+}
+
+/// The text of an HTML block, consuming its events up to the end of the block
+pub(crate) fn html_block<'a>(iter: &mut impl Iterator<Item = (Event<'a>, Range<usize>)>) -> String {
+    let mut html = String::new();
+    for (ev, _) in iter {
+        match ev {
+            Event::Html(t) => html.push_str(&t),
+            Event::End(TagEnd::HtmlBlock) => break,
+            _ => {}
+        }
+    }
+    html
+}
+
+/// Drops ignored regions and directive comments from the event stream
+///
+/// Directives are only honoured as standalone HTML blocks outside lists and
+/// quotes, so removing a region never leaves unbalanced start/end events.
+/// With `keys` set, `md2data:key` replaces the text of the next heading.
+pub(crate) fn apply_directives<'a>(
+    events: Vec<(Event<'a>, Range<usize>)>,
+    keys: bool,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut out = Vec::with_capacity(events.len());
+    let mut depth = 0usize;
+    let mut ignoring = false;
+    let mut pending_key: Option<String> = None;
+
+    let mut iter = events.into_iter();
+    while let Some((ev, range)) = iter.next() {
+        if depth == 0 && matches!(ev, Event::Start(Tag::HtmlBlock)) {
+            let mut html = String::new();
+            let mut block = vec![(ev, range)];
+            for (ev, range) in iter.by_ref() {
+                let end = matches!(ev, Event::End(TagEnd::HtmlBlock));
+                if let Event::Html(t) = &ev {
+                    html.push_str(t);
+                }
+                block.push((ev, range));
+                if end {
+                    break;
+                }
+            }
+
+            match parse_directive(&html) {
+                Some(Directive::Ignore) => ignoring = true,
+                Some(Directive::IgnoreEnd) => ignoring = false,
+                Some(Directive::Key(name)) if keys && !ignoring => pending_key = Some(name),
+                // Type hints and includes are applied by the parser
+                Some(Directive::Type(_) | Directive::Include(_)) if !ignoring => out.extend(block),
+                Some(_) => {}
+                None if ignoring => {}
+                None => out.extend(block),
+            }
+            continue;
+        }
+
+        match &ev {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+        if ignoring {
+            continue;
+        }
+
+        if matches!(ev, Event::Start(Tag::Heading { .. }))
+            && let Some(name) = pending_key.take() {
+            // Swap the heading's inline content for the overridden key. The name has no
+            // source text, so it gets an empty range instead of the heading's
+            out.push((ev, range.clone()));
+            out.push((Event::Text(name.into()), range.end..range.end));
+            for (ev, range) in iter.by_ref() {
+                if matches!(ev, Event::End(TagEnd::Heading(_))) {
+                    depth -= 1;
+                    out.push((ev, range));
+                    break;
+                }
+            }
+            continue;
+        }
+
+        out.push((ev, range));
+    }
+
+    out
+}
+
+impl TypeHint {
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if let Some(inner) = s.strip_prefix("list<").and_then(|s| s.strip_suffix('>')) {
+            return Some(TypeHint::List(Some(Box::new(TypeHint::parse(inner)?))));
+        }
+        match s {
+            "string" | "str" => Some(TypeHint::String),
+            "int" | "integer" => Some(TypeHint::Int),
+            "float" | "number" => Some(TypeHint::Float),
+            "bool" | "boolean" => Some(TypeHint::Bool),
+            "list" => Some(TypeHint::List(None)),
+            _ => None,
+        }
+    }
+
+    /// Converts `value` to this type, or describes why it cannot be converted
+    pub(crate) fn coerce(&self, value: MinifiedNode) -> Result<MinifiedNode, String> {
+        let fail = |value: &MinifiedNode| Err(format!("cannot coerce {} to {}", describe(value), self));
+
+        match (self, value) {
+            (TypeHint::List(inner), MinifiedNode::Array(items)) => match inner {
+                Some(inner) => items.into_iter()
+                    .map(|item| inner.coerce(item))
+                    .collect::<Result<_, _>>()
+                    .map(MinifiedNode::Array),
+                None => Ok(MinifiedNode::Array(items)),
+            },
+            (TypeHint::List(_), MinifiedNode::String(s)) if s.is_empty() => Ok(MinifiedNode::Array(vec![])),
+            (TypeHint::List(inner), value) => match inner {
+                Some(inner) => Ok(MinifiedNode::Array(vec![inner.coerce(value)?])),
+                None => Ok(MinifiedNode::Array(vec![value])),
+            },
+
+            (TypeHint::String, MinifiedNode::String(s)) => Ok(MinifiedNode::String(s)),
+            (TypeHint::String, MinifiedNode::Integer(i)) => Ok(MinifiedNode::String(i.to_string())),
+            (TypeHint::String, MinifiedNode::Float(f)) => Ok(MinifiedNode::String(f.to_string())),
+            (TypeHint::String, MinifiedNode::Bool(b)) => Ok(MinifiedNode::String(b.to_string())),
+
+            (TypeHint::Int, MinifiedNode::Integer(i)) => Ok(MinifiedNode::Integer(i)),
+            (TypeHint::Int, MinifiedNode::String(s)) => match s.trim().parse::<i64>() {
+                Ok(i) => Ok(MinifiedNode::Integer(i)),
+                Err(_) => fail(&MinifiedNode::String(s)),
+            },
+
+            (TypeHint::Float, MinifiedNode::Float(f)) => Ok(MinifiedNode::Float(f)),
+            (TypeHint::Float, MinifiedNode::Integer(i)) => Ok(MinifiedNode::Float(i as f64)),
+            (TypeHint::Float, MinifiedNode::String(s)) => match s.trim().parse::<f64>() {
+                Ok(f) if f.is_finite() => Ok(MinifiedNode::Float(f)),
+                _ => fail(&MinifiedNode::String(s)),
+            },
+
+            (TypeHint::Bool, MinifiedNode::Bool(b)) => Ok(MinifiedNode::Bool(b)),
+            (TypeHint::Bool, MinifiedNode::String(s)) => match s.trim() {
+                "true" => Ok(MinifiedNode::Bool(true)),
+                "false" => Ok(MinifiedNode::Bool(false)),
+                _ => fail(&MinifiedNode::String(s)),
+            },
+
+            (_, value) => fail(&value),
+        }
+    }
+}
+
+impl fmt::Display for TypeHint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeHint::String => f.write_str("string"),
+            TypeHint::Int => f.write_str("int"),
+            TypeHint::Float => f.write_str("float"),
+            TypeHint::Bool => f.write_str("bool"),
+            TypeHint::List(None) => f.write_str("list"),
+            TypeHint::List(Some(inner)) => write!(f, "list<{inner}>"),
+        }
+    }
+}
+
+fn describe(value: &MinifiedNode) -> String {
+    match value {
+        MinifiedNode::String(s) => format!("{s:?}"),
+        MinifiedNode::Bool(b) => b.to_string(),
+        MinifiedNode::Integer(i) => i.to_string(),
+        MinifiedNode::Float(f) => f.to_string(),
+        MinifiedNode::Null => "null".to_string(),
+        MinifiedNode::Array(_) => "a list".to_string(),
+        MinifiedNode::Map(_) => "a map".to_string(),
+    }
+}
@@ -0,0 +1,179 @@
+use std::ops::Range;
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use crate::ast::path_segments;
+use crate::directives::{html_block, parse_directive, Directive};
+use crate::ConvertError;
+
+/// A top-level piece of the document and where it is in the source
+enum Item {
+    Heading { level: u8, key: String },
+    /// Comments, directives and ignored regions are never part of a value, so they are kept
+    Block { keep: bool },
+}
+
+/// Replaces the value of the section at `path` and returns the edited Markdown
+///
+/// `path` is a heading path like `Server/Port` or a JSON Pointer. `value` is
+/// Markdown, e.g. `8080` or `- a\n- b`. Only the section's text is rewritten:
+/// the heading, comments and type hints under it, the surrounding whitespace
+/// and the rest of the document are kept byte for byte. Comments and ignored
+/// regions that sat between blocks of the old value follow the new one.
+pub fn set_section(input: &str, path: &str, value: &str) -> Result<String, ConvertError> {
+    let items = top_level_items(input);
+    let segments = path_segments(path);
+    let fail = |message: &str| ConvertError::Edit { path: path.to_string(), message: message.to_string() };
+
+    // Find the heading, tracking the path of every heading like the minified parser does
+    let mut stack: Vec<(u8, &str)> = vec![];
+    let mut found = None;
+    for (i, (item, _)) in items.iter().enumerate() {
+        if let Item::Heading { level, key } = item {
+            while stack.last().is_some_and(|(l, _)| l >= level) {
+                stack.pop();
+            }
+            stack.push((*level, key));
+            if stack.len() == segments.len() && stack.iter().zip(&segments).all(|((_, k), s)| k == s) {
+                found = Some(i);
+                break;
+            }
+        }
+    }
+    let Some(heading) = found else {
+        return Err(missing_heading(path, &segments, &items));
+    };
+
+    // The section's own content runs up to the next heading of any level
+    let content: Vec<&(Item, Range<usize>)> = items[heading + 1..].iter()
+        .take_while(|(item, _)| !matches!(item, Item::Heading { .. }))
+        .collect();
+    if let Some((Item::Heading { level, .. }, _)) = items.get(heading + 1 + content.len())
+        && let Item::Heading { level: own, .. } = &items[heading].0
+        && level > own {
+        return Err(fail("it has subsections, only sections without subheadings can be set"));
+    }
+
+    let value = value.trim_end_matches('\n');
+    let values: Vec<&Range<usize>> = content.iter()
+        .filter(|(item, _)| matches!(item, Item::Block { keep: false }))
+        .map(|(_, range)| range)
+        .collect();
+
+    let mut out = String::with_capacity(input.len() + value.len());
+    match (values.first(), values.last()) {
+        (Some(first), Some(last)) => {
+            // Keep the whitespace after the old value
+            let end = first.start + input[first.start..last.end].trim_end().len();
+            out.push_str(&input[..first.start]);
+            out.push_str(value);
+            // Comments and ignored regions between the old value's blocks move after
+            // the new value, runs of them without a value block in between stay as written
+            let mut runs: Vec<Range<usize>> = vec![];
+            let mut run: Option<Range<usize>> = None;
+            for (item, range) in content.iter().filter(|(_, range)| range.start > first.start && range.end <= end) {
+                if let Item::Block { keep: true } = item {
+                    run = Some(run.map_or(range.clone(), |run| run.start..range.end));
+                } else if let Some(run) = run.take() {
+                    runs.push(run);
+                }
+            }
+            runs.extend(run);
+            for run in runs {
+                out.push_str("\n\n");
+                out.push_str(input[run].trim_end());
+            }
+            out.push_str(&input[end..]);
+        }
+        _ => {
+            // Empty section: insert after the heading and any comments under it
+            let at = content.last().map_or(items[heading].1.end, |(_, range)| range.end);
+            out.push_str(&input[..at]);
+            if !input[..at].ends_with('\n') {
+                out.push('\n');
+            }
+            out.push_str(value);
+            out.push('\n');
+            out.push_str(&input[at..]);
+        }
+    }
+    Ok(out)
+}
+
+/// Headings and blocks outside of lists and quotes, with their source ranges
+///
+/// Built from the raw events rather than `apply_directives`, so ignored regions
+/// are still in the list and can be kept like comments.
+fn top_level_items(input: &str) -> Vec<(Item, Range<usize>)> {
+    let mut items = vec![];
+    let mut depth = 0usize;
+    let mut heading: Option<(u8, String, Range<usize>)> = None;
+    let mut ignoring = false;
+    let mut pending_key: Option<String> = None;
+
+    let mut iter = Parser::new(input).into_offset_iter();
+    while let Some((ev, range)) = iter.next() {
+        match ev {
+            Event::Start(Tag::HtmlBlock) if depth == 0 => {
+                match parse_directive(&html_block(&mut iter)) {
+                    Some(Directive::Ignore) => ignoring = true,
+                    Some(Directive::IgnoreEnd) => ignoring = false,
+                    Some(Directive::Key(name)) if !ignoring => pending_key = Some(name),
+                    _ => {}
+                }
+                items.push((Item::Block { keep: true }, range));
+            }
+            Event::Start(Tag::Heading { level, .. }) if depth == 0 && !ignoring => {
+                heading = Some((level as u8, String::new(), range));
+                depth += 1;
+            }
+            Event::End(TagEnd::Heading(_)) if depth == 1 && heading.is_some() => {
+                if let Some((level, key, range)) = heading.take() {
+                    let key = pending_key.take().unwrap_or_else(|| key.trim().to_string());
+                    items.push((Item::Heading { level, key }, range));
+                }
+                depth -= 1;
+            }
+            Event::Text(t) | Event::Code(t) => {
+                if let Some((_, key, _)) = &mut heading {
+                    key.push_str(&t);
+                }
+            }
+            Event::Start(_) => {
+                if depth == 0 {
+                    items.push((Item::Block { keep: ignoring }, range));
+                }
+                depth += 1;
+            }
+            Event::End(_) => depth -= 1,
+            Event::Rule if depth == 0 => items.push((Item::Block { keep: ignoring }, range)),
+            _ => {}
+        }
+    }
+    items
+}
+
+/// Names the first segment of `segments` that no heading matches
+fn missing_heading(path: &str, segments: &[String], items: &[(Item, Range<usize>)]) -> ConvertError {
+    let mut matched = 0;
+    let mut stack: Vec<(u8, &str)> = vec![];
+    for (item, _) in items {
+        if let Item::Heading { level, key } = item {
+            while stack.last().is_some_and(|(l, _)| l >= level) {
+                stack.pop();
+            }
+            stack.push((*level, key));
+            let depth = stack.iter().zip(segments).take_while(|((_, k), s)| k == *s).count();
+            if depth == stack.len() {
+                matched = matched.max(depth);
+            }
+        }
+    }
+
+    ConvertError::Select {
+        path: path.to_string(),
+        parent: match matched {
+            0 => "the document".to_string(),
+            n => format!("\"{}\"", segments[..n].join("/")),
+        },
+        segment: segments.get(matched).cloned().unwrap_or_default(),
+    }
+}
@@ -0,0 +1,52 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::ConvertError;
+
+/// Where `<!-- md2data:include path -->` looks for files
+#[derive(Debug, Clone)]
+pub struct IncludeOptions {
+    /// Included files must live under this directory
+    pub root: PathBuf,
+    /// The file being parsed, include paths are relative to its directory.
+    /// Paths are relative to `root` when there is no file (e.g. stdin)
+    pub origin: Option<PathBuf>,
+}
+
+impl IncludeOptions {
+    /// The files being included, outermost first, start with the origin so it can't include itself
+    pub(crate) fn chain(&self) -> Vec<PathBuf> {
+        self.origin.iter()
+            .filter_map(|p| p.canonicalize().ok())
+            .collect()
+    }
+
+    /// Reads the file `target` refers to, checking it is inside the root and not already being included
+    pub(crate) fn resolve(&self, target: &str, chain: &[PathBuf]) -> Result<(PathBuf, String), ConvertError> {
+        let fail = |message: String| ConvertError::Include { path: target.to_string(), message };
+
+        let root = self.root.canonicalize()
+            .map_err(|e| fail(format!("include root {}: {e}", self.root.display())))?;
+        let base = match &self.origin {
+            Some(origin) => origin.parent().map(Path::to_path_buf).unwrap_or_default(),
+            None => self.root.clone(),
+        };
+
+        let path = base.join(target).canonicalize().map_err(|e| fail(e.to_string()))?;
+        if !path.starts_with(&root) {
+            return Err(fail(format!("outside of the include root {}", root.display())));
+        }
+
+        if chain.contains(&path) {
+            let cycle = chain.iter()
+                .skip_while(|p| **p != path)
+                .chain([&path])
+                .map(|p| p.strip_prefix(&root).unwrap_or(p).display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(fail(format!("include cycle {cycle}")));
+        }
+
+        let text = fs::read_to_string(&path).map_err(|e| fail(e.to_string()))?;
+        Ok((path, text))
+    }
+}
@@ -1,24 +1,44 @@
 mod ast;
+mod delimited;
+mod directives;
+mod edit;
+mod includes;
+mod merge;
 mod parse;
 mod parse_minified;
+mod records;
 mod sanitizers;
+mod walk;
 
 use thiserror::Error;
 use serde::Serialize;
 
 pub use ast::{Node, NodeOrString, MinifiedNode};
+pub use delimited::CsvOptions;
+pub use edit::set_section;
+pub use includes::IncludeOptions;
+pub use merge::{merge_markdown, merge_nodes, MergeStrategy};
 pub use parse::parse_markdown;
-pub use parse_minified::parse_markdown_minified;
-pub use sanitizers::sanitize_keys;
+pub use parse_minified::{
+    parse_markdown_minified, parse_markdown_minified_with, CodeBlockFormat, EmptySection, LeafFormat,
+    ListItemFormat, MinifiedOptions, OrderedListFormat, SectionFormat, TitleMode,
+};
+pub use sanitizers::{
+    is_toml_bare_key, sanitize_key, sanitize_keys, sanitize_keys_for, transform_key, transform_keys,
+    CollisionPolicy, KeyCase, KeyProfile,
+};
+pub use walk::{expand_glob, parse_markdown_dir, DirOptions};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputFormat { 
     Json, 
     Yaml, 
+    /// TOML has no null, so null values are left out of maps and arrays alike
     Toml, 
     Xml 
 }
 impl OutputFormat {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_ascii_lowercase().as_str() {
             "json" => Some(Self::Json),
@@ -28,6 +48,27 @@ impl OutputFormat {
             _ => None,
         }
     }
+
+    /// File extension for output in this format, without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+            Self::Toml => "toml",
+            Self::Xml  => "xml",
+        }
+    }
+
+    /// Key naming rules applied automatically when converting to this format
+    ///
+    /// JSON and YAML accept any string as a key and TOML quotes keys that aren't
+    /// bare, so only XML has a profile.
+    pub fn key_profile(self) -> Option<KeyProfile> {
+        match self {
+            Self::Json | Self::Yaml | Self::Toml => None,
+            Self::Xml  => Some(KeyProfile::Xml),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -43,6 +84,75 @@ pub enum ConvertError {
     UnsupportedFormat,
     #[error("serialization: {0}")]
     Ser(String),
+    #[error("key collision: \"{first}\" and \"{second}\" both become \"{key}\"")]
+    KeyCollision { key: String, first: String, second: String },
+    #[error("invalid embedded {lang} under \"{key}\": {message}")]
+    EmbeddedData { key: String, lang: String, message: String },
+    #[error("type hint \"{hint}\" for \"{key}\": {message}")]
+    TypeHint { key: String, hint: String, message: String },
+    #[error("cannot include \"{path}\": {message}")]
+    Include { path: String, message: String },
+    #[error("merge conflict at \"{key}\"")]
+    MergeConflict { key: String },
+    #[error("{path}: {message}")]
+    File { path: String, message: String },
+    #[error("path \"{path}\" not found: {parent} has no \"{segment}\"")]
+    Select { path: String, parent: String, segment: String },
+    #[error("cannot set \"{path}\": {message}")]
+    Edit { path: String, message: String },
+}
+
+/// Extra knobs for [`convert_str_with`]. The default matches [`convert_str`].
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    /// Options for the Minified parser
+    pub minified: MinifiedOptions,
+    /// Only output the subtree at this path, see [`MinifiedNode::select`] (Minified mode only)
+    pub select: Option<String>,
+    /// Layout of the serialized text
+    pub style: OutputStyle,
+}
+
+/// Layout of the serialized text. The default matches [`convert_str`]
+///
+/// YAML is always written in block style with two space indentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OutputStyle {
+    /// Single-line JSON, inline TOML arrays, unindented XML
+    pub compact: bool,
+    /// Spaces per level for JSON (default 2) and XML (default: not indented)
+    pub indent: Option<usize>,
+    /// Sort map keys, so output doesn't depend on heading order (Minified mode only)
+    pub sort_keys: bool,
+    pub trailing_newline: TrailingNewline,
+}
+
+/// Whether the output ends with a line break
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingNewline {
+    /// Whatever the serializer produces (YAML and TOML end with one, JSON and XML don't)
+    #[default]
+    Keep,
+    Always,
+    Never,
+}
+
+impl TrailingNewline {
+    fn apply(self, mut out: String) -> String {
+        match self {
+            TrailingNewline::Keep => {}
+            TrailingNewline::Always => {
+                if !out.ends_with('\n') {
+                    out.push('\n');
+                }
+            }
+            TrailingNewline::Never => {
+                let len = out.trim_end_matches('\n').len();
+                out.truncate(len);
+            }
+        }
+        out
+    }
 }
 
 #[derive(Serialize)]
@@ -50,39 +160,78 @@ pub enum ConvertError {
 struct XmlRoot<'a>(&'a MinifiedNode);
 
 pub fn convert_str(input: &str, fmt: OutputFormat, mode: ParsingMode) -> Result<String, ConvertError> {
+    convert_str_with(input, fmt, mode, &ConvertOptions::default())
+}
+
+pub fn convert_str_with(
+    input: &str,
+    fmt: OutputFormat,
+    mode: ParsingMode,
+    opts: &ConvertOptions,
+) -> Result<String, ConvertError> {
     match mode {
         ParsingMode::Document => {
             let ast = parse_markdown(input);
-            serialize_ast(&ast, fmt)
+            serialize_ast(&ast, fmt, &opts.style)
         },
         ParsingMode::Minified => {
-            let mut ast = parse_markdown_minified(input);
+            let ast = parse_markdown_minified_with(input, &opts.minified)?;
+            convert_minified(ast, fmt, opts)
+        },
+    }
+}
 
-            #[cfg(feature = "xml")]
-            if let OutputFormat::Xml = fmt {
-                // Sanitize XML keys
-                ast = sanitize_keys(ast);
+/// Serializes an already parsed Minified tree, e.g. from [`merge_markdown`]
+///
+/// Heading keys were already rewritten by the parser, only `key_collisions` of the
+/// parser options is used here, for keys the output format has to rename.
+pub fn convert_minified(mut ast: MinifiedNode, fmt: OutputFormat, opts: &ConvertOptions) -> Result<String, ConvertError> {
+    if let Some(path) = &opts.select {
+        ast = ast.select(path)?.clone();
 
-                // Add root <Document></Document> wrapper so XML conversion works
-                let wrapper = XmlRoot(&ast);
-                return quick_xml::se::to_string(&wrapper)
-                    .map_err(|e| ConvertError::Ser(e.to_string()));
-            }
+        // A TOML document is a table, a selected list is wrapped like split records below
+        if fmt == OutputFormat::Toml && !matches!(ast, MinifiedNode::Map(_) | MinifiedNode::Array(_)) {
+            return Err(ConvertError::Ser(format!(
+                "TOML needs a table at the top, \"{path}\" selects a single value"
+            )));
+        }
+    }
 
-            #[cfg(not(feature = "xml"))]
-            if let OutputFormat::Xml = fmt {
-                return Err(ConvertError::UnsupportedFormat);
-            }
+    // Sanitize keys for formats with naming rules (XML tags)
+    if let Some(profile) = fmt.key_profile() {
+        ast = sanitize_keys_for(ast, profile, opts.minified.key_collisions)?;
+    }
 
-            serialize_ast(&ast, fmt)
-        },
+    // Split records: TOML needs a table at the top, XML a single root element
+    if let (OutputFormat::Toml | OutputFormat::Xml, MinifiedNode::Array(_)) = (fmt, &ast) {
+        ast = MinifiedNode::Map(vec![("record".to_string(), ast)]);
     }
+
+    if opts.style.sort_keys {
+        ast.sort_keys();
+    }
+
+    if let OutputFormat::Toml = fmt {
+        ast.drop_nulls();
+    }
+
+    #[cfg(feature = "xml")]
+    if let OutputFormat::Xml = fmt {
+        // Add root <Document></Document> wrapper so XML conversion works
+        return serialize_ast(&XmlRoot(&ast), fmt, &opts.style);
+    }
+
+    #[cfg(not(feature = "xml"))]
+    if let OutputFormat::Xml = fmt {
+        return Err(ConvertError::UnsupportedFormat);
+    }
+
+    serialize_ast(&ast, fmt, &opts.style)
 }
 
-fn serialize_ast<T: Serialize>(ast: &T, fmt: OutputFormat) -> Result<String, ConvertError> {
-    match fmt {
-        OutputFormat::Json => serde_json::to_string_pretty(&ast)
-            .map_err(|e| ConvertError::Ser(e.to_string())),
+fn serialize_ast<T: Serialize>(ast: &T, fmt: OutputFormat, style: &OutputStyle) -> Result<String, ConvertError> {
+    let out = match fmt {
+        OutputFormat::Json => to_json(ast, style),
 
         #[cfg(feature="yaml")]
         OutputFormat::Yaml => serde_yaml::to_string(&ast)
@@ -91,6 +240,10 @@ fn serialize_ast<T: Serialize>(ast: &T, fmt: OutputFormat) -> Result<String, Con
         #[cfg(not(feature="yaml"))]
         OutputFormat::Yaml => Err(ConvertError::UnsupportedFormat),
 
+        #[cfg(feature="toml")]
+        OutputFormat::Toml if style.compact => toml::to_string(&ast)
+            .map_err(|e| ConvertError::Ser(e.to_string())),
+
         #[cfg(feature="toml")]
         OutputFormat::Toml => toml::to_string_pretty(&ast)
             .map_err(|e| ConvertError::Ser(e.to_string())),
@@ -99,10 +252,35 @@ fn serialize_ast<T: Serialize>(ast: &T, fmt: OutputFormat) -> Result<String, Con
         OutputFormat::Toml => Err(ConvertError::UnsupportedFormat),
 
         #[cfg(feature="xml")]
-        OutputFormat::Xml => quick_xml::se::to_string(&ast)
-            .map_err(|e| ConvertError::Ser(e.to_string())),
+        OutputFormat::Xml => to_xml(ast, style),
 
         #[cfg(not(feature="xml"))]
         OutputFormat::Xml => Err(ConvertError::UnsupportedFormat),
+    }?;
+    Ok(style.trailing_newline.apply(out))
+}
+
+fn to_json<T: Serialize>(ast: &T, style: &OutputStyle) -> Result<String, ConvertError> {
+    if style.compact {
+        return serde_json::to_string(ast).map_err(|e| ConvertError::Ser(e.to_string()));
+    }
+
+    let indent = " ".repeat(style.indent.unwrap_or(2));
+    let mut out = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    ast.serialize(&mut serde_json::Serializer::with_formatter(&mut out, formatter))
+        .map_err(|e| ConvertError::Ser(e.to_string()))?;
+    // serde_json only writes valid UTF-8
+    Ok(String::from_utf8(out).expect("JSON output is UTF-8"))
+}
+
+#[cfg(feature="xml")]
+fn to_xml<T: Serialize>(ast: &T, style: &OutputStyle) -> Result<String, ConvertError> {
+    let mut out = String::new();
+    let mut ser = quick_xml::se::Serializer::new(&mut out);
+    if let Some(indent) = style.indent.filter(|_| !style.compact) {
+        ser.indent(' ', indent);
     }
-}
\ No newline at end of file
+    ast.serialize(ser).map_err(|e| ConvertError::Ser(e.to_string()))?;
+    Ok(out)
+}
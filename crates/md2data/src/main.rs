@@ -1,8 +1,22 @@
-use clap::{Parser, ValueEnum};
-use std::{fs, io::{self, Read}};
-use md2data::{convert_str, OutputFormat, ParsingMode};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+use md2data::{
+    convert_minified, convert_str_with, expand_glob, merge_nodes, parse_markdown_dir, parse_markdown_minified_with,
+    set_section, CodeBlockFormat, CollisionPolicy, ConvertOptions, CsvOptions, DirOptions, EmptySection,
+    IncludeOptions, KeyCase, LeafFormat, ListItemFormat, MergeStrategy, MinifiedNode, MinifiedOptions,
+    OrderedListFormat, OutputFormat, OutputStyle, ParsingMode, SectionFormat, TitleMode,
+    TrailingNewline,
+};
 
-#[derive(Clone, ValueEnum)]
+#[derive(Clone, Copy, ValueEnum)]
 enum Format { 
     Json, 
     Yaml, 
@@ -20,50 +34,777 @@ impl From<Format> for OutputFormat {
     }
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum Newline {
+    Keep,
+    Always,
+    Never,
+}
+impl From<Newline> for TrailingNewline {
+    fn from(n: Newline) -> Self {
+        match n {
+            Newline::Keep   => TrailingNewline::Keep,
+            Newline::Always => TrailingNewline::Always,
+            Newline::Never  => TrailingNewline::Never,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Case {
+    Snake,
+    Camel,
+    Kebab,
+    ScreamingSnake,
+    Slug,
+}
+impl From<Case> for KeyCase {
+    fn from(c: Case) -> Self {
+        match c {
+            Case::Snake          => KeyCase::Snake,
+            Case::Camel          => KeyCase::Camel,
+            Case::Kebab          => KeyCase::Kebab,
+            Case::ScreamingSnake => KeyCase::ScreamingSnake,
+            Case::Slug           => KeyCase::Slug,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Collisions {
+    Error,
+    Rename,
+}
+impl From<Collisions> for CollisionPolicy {
+    fn from(c: Collisions) -> Self {
+        match c {
+            Collisions::Error  => CollisionPolicy::Error,
+            Collisions::Rename => CollisionPolicy::Disambiguate,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Conflict {
+    LaterWins,
+    Error,
+    ConcatArrays,
+}
+impl From<Conflict> for MergeStrategy {
+    fn from(c: Conflict) -> Self {
+        match c {
+            Conflict::LaterWins    => MergeStrategy::LaterWins,
+            Conflict::Error        => MergeStrategy::Error,
+            Conflict::ConcatArrays => MergeStrategy::ConcatArrays,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Leaf {
+    Plain,
+    Markdown,
+    Html,
+}
+impl From<Leaf> for LeafFormat {
+    fn from(l: Leaf) -> Self {
+        match l {
+            Leaf::Plain    => LeafFormat::Plain,
+            Leaf::Markdown => LeafFormat::Markdown,
+            Leaf::Html     => LeafFormat::Html,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Sections {
+    Joined,
+    Blocks,
+}
+impl From<Sections> for SectionFormat {
+    fn from(s: Sections) -> Self {
+        match s {
+            Sections::Joined => SectionFormat::Joined,
+            Sections::Blocks => SectionFormat::Blocks,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Code {
+    Fenced,
+    Object,
+    Raw,
+}
+impl From<Code> for CodeBlockFormat {
+    fn from(c: Code) -> Self {
+        match c {
+            Code::Fenced => CodeBlockFormat::Fenced,
+            Code::Object => CodeBlockFormat::Object,
+            Code::Raw    => CodeBlockFormat::Raw,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ListItems {
+    Flatten,
+    Keyed,
+    TextChildren,
+}
+impl From<ListItems> for ListItemFormat {
+    fn from(l: ListItems) -> Self {
+        match l {
+            ListItems::Flatten      => ListItemFormat::Flatten,
+            ListItems::Keyed        => ListItemFormat::Keyed,
+            ListItems::TextChildren => ListItemFormat::TextChildren,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Ordered {
+    Array,
+    Object,
+}
+impl From<Ordered> for OrderedListFormat {
+    fn from(o: Ordered) -> Self {
+        match o {
+            Ordered::Array  => OrderedListFormat::Array,
+            Ordered::Object => OrderedListFormat::Object,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Empty {
+    String,
+    Null,
+    Map,
+    Omit,
+}
+impl From<Empty> for EmptySection {
+    fn from(e: Empty) -> Self {
+        match e {
+            Empty::String => EmptySection::EmptyString,
+            Empty::Null   => EmptySection::Null,
+            Empty::Map    => EmptySection::EmptyMap,
+            Empty::Omit   => EmptySection::Omit,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Title {
+    Keep,
+    Hoist,
+    Promote,
+}
+impl From<Title> for TitleMode {
+    fn from(t: Title) -> Self {
+        match t {
+            Title::Keep    => TitleMode::Keep,
+            Title::Hoist   => TitleMode::Hoist,
+            Title::Promote => TitleMode::Promote,
+        }
+    }
+}
+
 #[derive(Parser)]
-#[command(author, version, about = "Markdown → JSON/YAML/TOML/XML")]
+#[command(
+    author,
+    version,
+    about = "Markdown → JSON/YAML/TOML/XML",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    args: Args,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Re-convert inputs into --out-dir whenever they change on disk
+    Watch(WatchArgs),
+    /// Print the value at a heading path, scalars without quotes
+    Get {
+        /// Markdown file (use '-' for stdin)
+        file: String,
+        /// Heading path (A/B/C) or JSON Pointer (/A/B/0)
+        path: String,
+    },
+    /// Replace the text of the section at a heading path, editing the file in place
+    Set {
+        /// Markdown file
+        file: String,
+        /// Heading path (A/B/C) or JSON Pointer (/A/B)
+        path: String,
+        /// New value, as Markdown
+        value: String,
+    },
+}
+
+#[derive(clap::Args)]
+struct WatchArgs {
+    /// Input files or quoted globs, globs also pick up files created later
+    #[arg(required = true)]
+    inputs: Vec<String>,
+
+    /// Directory the <name>.<ext> files are written to
+    #[arg(long, value_name = "DIR")]
+    out_dir: String,
+
+    /// Milliseconds to wait after a file's last change before converting it
+    #[arg(long, value_name = "MS", default_value_t = 200)]
+    debounce: u64,
+
+    #[command(flatten)]
+    convert: ConvertArgs,
+}
+
+#[derive(clap::Args)]
 struct Args {
-    /// Input file (use '-' for stdin)
-    input: String,
+    /// Input files or quoted globs (use '-' for stdin), more than one needs --merge or --out-dir
+    #[arg(required_unless_present = "dir")]
+    inputs: Vec<String>,
+
+    /// Convert every Markdown file under a directory into one object keyed by path
+    #[arg(long, conflicts_with_all = ["inputs", "structured"])]
+    dir: Option<String>,
+
+    /// Only convert files under --dir matching this glob (repeatable, default: every .md and .markdown file)
+    #[arg(long, value_name = "GLOB", requires = "dir")]
+    glob: Vec<String>,
+
+    /// Skip files under --dir matching this glob (repeatable)
+    #[arg(long, value_name = "GLOB", requires = "dir")]
+    exclude: Vec<String>,
 
-    /// Output format
-    #[arg(short, long, default_value_t = Format::Json, value_enum)]
-    format: Format,
+    /// Deep-merge all inputs into one document, later files override earlier ones.
+    /// With --split-on-break the record lists are replaced, or appended with --on-conflict concat-arrays
+    #[arg(long, conflicts_with = "structured")]
+    merge: bool,
+
+    /// What --merge does when files give a key different values
+    #[arg(long, default_value_t = Conflict::LaterWins, value_enum, requires = "merge")]
+    on_conflict: Conflict,
 
     /// Output file (defaults to stdout)
     #[arg(short, long)]
     out: Option<String>,
 
+    /// Convert each input separately into <DIR>/<name>.<ext>, in parallel
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["out", "merge", "dir"])]
+    out_dir: Option<String>,
+
+    #[command(flatten)]
+    convert: ConvertArgs,
+}
+
+/// Options shared by every way of converting
+#[derive(clap::Args)]
+struct ConvertArgs {
+    /// Output format (defaults to the --out extension, or json)
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+
+    /// Write JSON on one line, TOML arrays inline and XML without indentation
+    #[arg(long, conflicts_with = "indent")]
+    compact: bool,
+
+    /// Spaces per indentation level for JSON and XML
+    #[arg(long, value_name = "N")]
+    indent: Option<usize>,
+
+    /// Only output the section at a heading path (A/B/C) or JSON Pointer (/A/B/0) (minified mode only)
+    #[arg(long, value_name = "PATH", conflicts_with = "structured")]
+    select: Option<String>,
+
+    /// Sort keys for stable diffs (minified mode only)
+    #[arg(long)]
+    sort_keys: bool,
+
+    /// Whether the output ends with a line break (keep: stdout always gets one)
+    #[arg(long, default_value_t = Newline::Keep, value_enum)]
+    trailing_newline: Newline,
+
     /// Use structured/document mode (default is minified)
     #[arg(short = 's', long)]
     structured: bool,
+
+    /// Rewrite heading keys into a case style (minified mode only)
+    #[arg(long, value_enum)]
+    key_case: Option<Case>,
+
+    /// What to do when two keys become identical after rewriting
+    #[arg(long, default_value_t = Collisions::Error, value_enum)]
+    on_key_collision: Collisions,
+
+    /// How inline Markdown in text is rendered (minified mode only)
+    #[arg(long, default_value_t = Leaf::Plain, value_enum)]
+    leaf: Leaf,
+
+    /// Join a section's blocks into one string, or keep them as an array (minified mode only)
+    #[arg(long, default_value_t = Sections::Joined, value_enum)]
+    sections: Sections,
+
+    /// Keep code blocks fenced, or emit them as {lang, code} objects or raw code (minified mode only)
+    #[arg(long, default_value_t = Code::Fenced, value_enum)]
+    code: Code,
+
+    /// How list items with text and a nested list are represented (minified mode only)
+    #[arg(long, default_value_t = ListItems::Flatten, value_enum)]
+    list_items: ListItems,
+
+    /// Emit numbered lists as plain arrays, or as {ordered, start, items} objects (minified mode only)
+    #[arg(long, default_value_t = Ordered::Array, value_enum)]
+    ordered_lists: Ordered,
+
+    /// What a heading with no content becomes (minified mode only)
+    #[arg(long, default_value_t = Empty::String, value_enum)]
+    empty_sections: Empty,
+
+    /// Only the top N heading levels become keys, deeper ones are kept as text (minified mode only)
+    #[arg(long)]
+    max_depth: Option<NonZeroUsize>,
+
+    /// Treat a single top-level heading as the document title (minified mode only)
+    #[arg(long, default_value_t = Title::Keep, value_enum)]
+    title: Title,
+
+    /// Split the file into a list of records at thematic breaks and front matter blocks (minified mode only)
+    #[arg(long)]
+    split_on_break: bool,
+
+    /// Resolve include directives, only allowing files under this directory (minified mode only)
+    #[arg(long, value_name = "ROOT")]
+    includes: Option<String>,
+
+    /// Parse ```json, ```yaml and ```toml blocks into data (minified mode only)
+    #[arg(long)]
+    embedded_data: bool,
+
+    /// Parse ```csv and ```tsv blocks into arrays of records (minified mode only)
+    #[arg(long)]
+    csv: bool,
+
+    /// Field separator for --csv, overriding ',' for csv and tab for tsv
+    #[arg(long, requires = "csv")]
+    csv_delimiter: Option<char>,
+
+    /// Turn numbers, booleans and empty fields into typed values for --csv
+    #[arg(long, requires = "csv")]
+    csv_infer_types: bool,
+}
+
+impl ConvertArgs {
+    /// Select mode: default is Minified, use --structured for Document mode
+    fn mode(&self) -> ParsingMode {
+        if self.structured {
+            ParsingMode::Document
+        } else {
+            ParsingMode::Minified
+        }
+    }
+
+    /// `--format`, or the format matching the extension of `out`, or JSON
+    fn output_format(&self, out: Option<&str>) -> anyhow::Result<OutputFormat> {
+        let from_extension = out
+            .and_then(|path| Path::new(path).extension())
+            .and_then(|ext| OutputFormat::from_str(&ext.to_string_lossy()));
+
+        match (self.format.map(OutputFormat::from), from_extension) {
+            (Some(format), Some(inferred)) if format != inferred => anyhow::bail!(
+                "--format {} conflicts with the extension of {}",
+                format.extension(),
+                out.unwrap_or_default(),
+            ),
+            (Some(format), _) | (None, Some(format)) => Ok(format),
+            (None, None) => Ok(OutputFormat::Json),
+        }
+    }
+
+    fn options(&self) -> ConvertOptions {
+        ConvertOptions {
+            minified: MinifiedOptions {
+                leaf_format: self.leaf.into(),
+                section_format: self.sections.into(),
+                code_format: self.code.into(),
+                list_items: self.list_items.into(),
+                ordered_lists: self.ordered_lists.into(),
+                empty_sections: self.empty_sections.into(),
+                max_depth: self.max_depth,
+                title_mode: self.title.into(),
+                split_on_break: self.split_on_break,
+                includes: self.includes.as_ref().map(|root| IncludeOptions { root: root.into(), origin: None }),
+                embedded_data: self.embedded_data,
+                csv: self.csv.then_some(CsvOptions {
+                    delimiter: self.csv_delimiter,
+                    infer_types: self.csv_infer_types,
+                }),
+                key_case: self.key_case.map(Into::into),
+                key_collisions: self.on_key_collision.into(),
+            },
+            select: self.select.clone(),
+            style: OutputStyle {
+                compact: self.compact,
+                indent: self.indent,
+                sort_keys: self.sort_keys,
+                trailing_newline: self.trailing_newline.into(),
+            },
+        }
+    }
 }
 
 fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Watch(watch_args)) => return watch(watch_args),
+        Some(Command::Get { file, path }) => return get(&file, &path),
+        Some(Command::Set { file, path, value }) => return set(&file, &path, &value),
+        None => {}
+    }
 
-    let md = if args.input == "-" {
-        let mut s = String::new();
-        io::stdin().read_to_string(&mut s)?;
-        s
-    } else {
-        fs::read_to_string(&args.input)?
-    };
+    let args = cli.args;
+    let inputs = expand_inputs(&args.inputs)?;
+
+    if inputs.len() > 1 && !args.merge && args.out_dir.is_none() {
+        anyhow::bail!("multiple inputs need --merge or --out-dir");
+    }
+
+    let mode = args.convert.mode();
+    let opts = args.convert.options();
+    let format = args.convert.output_format(args.out.as_deref())?;
+
+    if let Some(out_dir) = &args.out_dir {
+        return convert_batch(&inputs, out_dir.as_ref(), format, mode, &opts);
+    }
 
-    // Select mode: default is Minified, use --structured for Document mode
-    let mode = if args.structured {
-        ParsingMode::Document
+    let out = if let Some(dir) = &args.dir {
+        let dir_opts = DirOptions { include: args.glob.clone(), exclude: args.exclude.clone() };
+        parse_markdown_dir(dir.as_ref(), &dir_opts, &opts.minified)
+            .and_then(|ast| convert_minified(ast, format, &opts))
+    } else if args.merge {
+        let mut merged: Option<MinifiedNode> = None;
+        for input in &inputs {
+            let node = parse_markdown_minified_with(&read_input(input)?, &for_input(&opts, input).minified)
+                .map_err(|e| anyhow::anyhow!("{input}: {e}"))?;
+            merged = Some(match merged {
+                Some(merged) => merge_nodes(merged, node, args.on_conflict.into())
+                    .map_err(|e| anyhow::anyhow!(e.to_string()))?,
+                None => node,
+            });
+        }
+        convert_minified(merged.unwrap_or(MinifiedNode::Map(vec![])), format, &opts)
     } else {
-        ParsingMode::Minified
+        convert_str_with(&read_input(&inputs[0])?, format, mode, &for_input(&opts, &inputs[0]))
     };
-
-    let out = convert_str(&md, args.format.into(), mode)
-        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    let out = out.map_err(|e| anyhow::anyhow!(e.to_string()))?;
 
     if let Some(p) = args.out {
         fs::write(p, out)?;
-    } else {
+    } else if opts.style.trailing_newline == TrailingNewline::Keep {
         println!("{out}");
+    } else {
+        print!("{out}");
     }
     Ok(())
 }
+
+fn read_input(input: &str) -> io::Result<String> {
+    if input == "-" {
+        let mut s = String::new();
+        io::stdin().read_to_string(&mut s)?;
+        Ok(s)
+    } else {
+        fs::read_to_string(input)
+    }
+}
+
+/// Includes resolve relative to the file being converted
+fn for_input(opts: &ConvertOptions, input: &str) -> ConvertOptions {
+    let mut opts = opts.clone();
+    if let Some(includes) = &mut opts.minified.includes
+        && input != "-" {
+        includes.origin = Some(input.into());
+    }
+    opts
+}
+
+/// Expands quoted globs, shells usually expand unquoted ones already
+fn expand_inputs(inputs: &[String]) -> anyhow::Result<Vec<String>> {
+    let mut expanded = vec![];
+    for input in inputs {
+        if input == "-" {
+            expanded.push(input.clone());
+            continue;
+        }
+        let paths = expand_glob(input).map_err(|e| anyhow::anyhow!("{input}: {e}"))?;
+        if paths.is_empty() {
+            anyhow::bail!("no files match {input}");
+        }
+        expanded.extend(paths.into_iter().map(|p| p.display().to_string()));
+    }
+    Ok(expanded)
+}
+
+/// Converts every input into its own file under `out_dir`, reporting failures per file
+fn convert_batch(
+    inputs: &[String],
+    out_dir: &Path,
+    format: OutputFormat,
+    mode: ParsingMode,
+    opts: &ConvertOptions,
+) -> anyhow::Result<()> {
+    let jobs = output_paths(inputs, out_dir, format)?;
+    fs::create_dir_all(out_dir)?;
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let per_thread = jobs.len().div_ceil(threads);
+    let results: Vec<(&str, anyhow::Result<()>)> = thread::scope(|s| {
+        let handles: Vec<_> = jobs.chunks(per_thread)
+            .map(|chunk| s.spawn(move || {
+                chunk.iter()
+                    .map(|(input, out)| (*input, convert_file(input, out, format, mode, opts)))
+                    .collect::<Vec<_>>()
+            }))
+            .collect();
+        handles.into_iter()
+            .flat_map(|handle| handle.join().expect("conversion thread panicked"))
+            .collect()
+    });
+
+    let mut failed = 0;
+    for (input, result) in &results {
+        if let Err(e) = result {
+            eprintln!("{input}: {e}");
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} files failed", results.len());
+    }
+    Ok(())
+}
+
+/// Where each input is written to under `out_dir`, failing on names that clash
+fn output_paths<'a>(inputs: &'a [String], out_dir: &Path, format: OutputFormat) -> anyhow::Result<Vec<(&'a str, PathBuf)>> {
+    if inputs.iter().any(|input| input == "-") {
+        anyhow::bail!("stdin can't be used with --out-dir");
+    }
+
+    let mut jobs: Vec<(&str, PathBuf)> = vec![];
+    for input in inputs {
+        let Some(stem) = Path::new(input).file_stem() else {
+            anyhow::bail!("{input}: not a file");
+        };
+        let mut name = stem.to_os_string();
+        name.push(".");
+        name.push(format.extension());
+        let out = out_dir.join(name);
+
+        if let Some((other, _)) = jobs.iter().find(|(_, o)| *o == out) {
+            anyhow::bail!("{other} and {input} would both be written to {}", out.display());
+        }
+        jobs.push((input, out));
+    }
+    Ok(jobs)
+}
+
+fn convert_file(input: &str, out: &Path, format: OutputFormat, mode: ParsingMode, opts: &ConvertOptions) -> anyhow::Result<()> {
+    let md = fs::read_to_string(input)?;
+    let converted = convert_str_with(&md, format, mode, &for_input(opts, input))
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    write_atomic(out, &converted)?;
+    Ok(())
+}
+
+/// Writes through a temporary file next to `path`, so readers never see half a file
+///
+/// An existing file keeps its permissions, and a symlink is followed so the link stays.
+fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut tmp = path.as_os_str().to_os_string();
+    tmp.push(".tmp");
+    fs::write(&tmp, contents)?;
+    if let Ok(meta) = fs::metadata(&path) {
+        fs::set_permissions(&tmp, meta.permissions())?;
+    }
+    fs::rename(&tmp, &path)
+}
+
+/// Prints scalars as plain text for shell scripts, anything else as JSON
+fn get(file: &str, path: &str) -> anyhow::Result<()> {
+    let ast = parse_markdown_minified_with(&read_input(file)?, &MinifiedOptions::default())
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    let node = ast.select(path).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+    match node {
+        MinifiedNode::String(s) => println!("{s}"),
+        MinifiedNode::Bool(b) => println!("{b}"),
+        MinifiedNode::Integer(i) => println!("{i}"),
+        MinifiedNode::Float(f) => println!("{f}"),
+        MinifiedNode::Null => println!("null"),
+        MinifiedNode::Array(_) | MinifiedNode::Map(_) => println!("{}", serde_json::to_string_pretty(node)?),
+    }
+    Ok(())
+}
+
+fn set(file: &str, path: &str, value: &str) -> anyhow::Result<()> {
+    let md = fs::read_to_string(file)?;
+    let edited = set_section(&md, path, value).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    write_atomic(Path::new(file), &edited)?;
+    Ok(())
+}
+
+/// Converts every input once, then again each time it changes, until interrupted
+///
+/// Files are polled for a new modification time. A file is converted once it
+/// has been left alone for the debounce time, so an editor's burst of writes
+/// only triggers one conversion.
+/// Tracks when each watched file last changed, so a file is converted once it has settled
+struct Debouncer {
+    delay: Duration,
+    seen: HashMap<String, SystemTime>,
+    /// Files changed since their last conversion, and when the change was noticed
+    pending: HashMap<String, Instant>,
+}
+
+impl Debouncer {
+    fn new(delay: Duration) -> Self {
+        Debouncer { delay, seen: HashMap::new(), pending: HashMap::new() }
+    }
+
+    /// Records the modification time of `input`, a new one (re)starts its wait
+    fn observe(&mut self, input: &str, modified: SystemTime, now: Instant) {
+        if self.seen.get(input) != Some(&modified) {
+            self.seen.insert(input.to_string(), modified);
+            self.pending.insert(input.to_string(), now);
+        }
+    }
+
+    /// Whether `input` changed at least `delay` ago, true once per change
+    fn ready(&mut self, input: &str, now: Instant) -> bool {
+        match self.pending.get(input) {
+            Some(changed) if now.duration_since(*changed) >= self.delay => {
+                self.pending.remove(input);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn watch(args: WatchArgs) -> anyhow::Result<()> {
+    const POLL: Duration = Duration::from_millis(100);
+
+    let mut changes = Debouncer::new(Duration::from_millis(args.debounce));
+    let out_dir = PathBuf::from(&args.out_dir);
+    let mode = args.convert.mode();
+    let opts = args.convert.options();
+    let format = args.convert.output_format(None)?;
+    fs::create_dir_all(&out_dir)?;
+
+    let mut last_error = String::new();
+
+    eprintln!("watching {} for changes, press Ctrl-C to stop", args.inputs.join(" "));
+    loop {
+        // Globs are expanded every round to notice new files
+        let jobs = expand_inputs(&args.inputs)
+            .and_then(|inputs| Ok(output_paths(&inputs, &out_dir, format)?
+                .into_iter()
+                .map(|(input, out)| (input.to_string(), out))
+                .collect::<Vec<_>>()));
+        let jobs = match jobs {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                // Report once, not on every poll
+                if e.to_string() != last_error {
+                    eprintln!("error: {e}");
+                    last_error = e.to_string();
+                }
+                thread::sleep(POLL);
+                continue;
+            }
+        };
+        last_error.clear();
+
+        for (input, _) in &jobs {
+            // Missing files are picked up once they appear
+            let Ok(modified) = fs::metadata(input).and_then(|m| m.modified()) else { continue };
+            changes.observe(input, modified, Instant::now());
+        }
+
+        let now = Instant::now();
+        for (input, out) in &jobs {
+            if !changes.ready(input, now) {
+                continue;
+            }
+
+            match convert_file(input, out, format, mode, &opts) {
+                Ok(()) => eprintln!("{input} -> {}", out.display()),
+                Err(e) => eprintln!("{input}: {e}"),
+            }
+        }
+
+        thread::sleep(POLL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output_format(args: &[&str], out: Option<&str>) -> anyhow::Result<OutputFormat> {
+        let cli = Cli::try_parse_from(["md2data", "in.md"].iter().chain(args)).unwrap();
+        cli.args.convert.output_format(out)
+    }
+
+    #[test]
+    fn test_output_format_from_extension() {
+        assert_eq!(output_format(&[], Some("x.yml")).unwrap(), OutputFormat::Yaml);
+        assert_eq!(output_format(&[], Some("x.TOML")).unwrap(), OutputFormat::Toml);
+        assert_eq!(output_format(&["-f", "xml"], Some("x.xml")).unwrap(), OutputFormat::Xml);
+
+        // Unknown or missing extensions fall back to --format, then JSON
+        assert_eq!(output_format(&[], Some("x.txt")).unwrap(), OutputFormat::Json);
+        assert_eq!(output_format(&[], None).unwrap(), OutputFormat::Json);
+        assert_eq!(output_format(&["-f", "toml"], Some("x.txt")).unwrap(), OutputFormat::Toml);
+    }
+
+    #[test]
+    fn test_debouncer_waits_for_changes_to_settle() {
+        let mut changes = Debouncer::new(Duration::from_millis(200));
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mtime = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+
+        // New files are converted once the delay has passed, and only once
+        changes.observe("a.md", mtime(1), at(0));
+        assert!(!changes.ready("a.md", at(100)));
+        assert!(changes.ready("a.md", at(200)));
+        assert!(!changes.ready("a.md", at(300)));
+
+        // An unchanged modification time is not a change
+        changes.observe("a.md", mtime(1), at(400));
+        assert!(!changes.ready("a.md", at(700)));
+
+        // Another change during the wait starts it over
+        changes.observe("a.md", mtime(2), at(800));
+        changes.observe("a.md", mtime(3), at(900));
+        assert!(!changes.ready("a.md", at(1000)));
+        assert!(changes.ready("a.md", at(1100)));
+
+        assert!(!changes.ready("unknown.md", at(5000)));
+    }
+
+    #[test]
+    fn test_output_format_conflict() {
+        let err = output_format(&["-f", "json"], Some("x.yaml")).unwrap_err();
+        assert_eq!(err.to_string(), "--format json conflicts with the extension of x.yaml");
+    }
+}
@@ -0,0 +1,79 @@
+use crate::ast::MinifiedNode;
+use crate::parse_minified::{parse_markdown_minified_with, MinifiedOptions};
+use crate::ConvertError;
+
+/// What to do when two sources give a key different values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// The later source replaces the earlier value
+    #[default]
+    LaterWins,
+    /// Fail, naming the conflicting key. Identical values are not a conflict
+    Error,
+    /// Append arrays to each other, otherwise the later source wins
+    ConcatArrays,
+}
+
+/// Parses each input and deep-merges the trees, later inputs overriding earlier ones
+///
+/// With `split_on_break` every input is an array of records, so `strategy` decides
+/// between them like any other arrays: `ConcatArrays` appends the records.
+pub fn merge_markdown(
+    inputs: &[&str],
+    opts: &MinifiedOptions,
+    strategy: MergeStrategy,
+) -> Result<MinifiedNode, ConvertError> {
+    let mut merged: Option<MinifiedNode> = None;
+    for input in inputs {
+        let node = parse_markdown_minified_with(input, opts)?;
+        merged = Some(match merged {
+            Some(merged) => merge_nodes(merged, node, strategy)?,
+            None => node,
+        });
+    }
+    Ok(merged.unwrap_or(MinifiedNode::Map(vec![])))
+}
+
+/// Deep-merges `overlay` into `base`
+///
+/// Maps are merged key by key, keeping the order keys first appear in.
+/// Any other pair of values is resolved by `strategy`.
+pub fn merge_nodes(
+    base: MinifiedNode,
+    overlay: MinifiedNode,
+    strategy: MergeStrategy,
+) -> Result<MinifiedNode, ConvertError> {
+    merge_at(base, overlay, strategy, &mut vec![])
+}
+
+fn merge_at(
+    base: MinifiedNode,
+    overlay: MinifiedNode,
+    strategy: MergeStrategy,
+    path: &mut Vec<String>,
+) -> Result<MinifiedNode, ConvertError> {
+    match (base, overlay) {
+        (MinifiedNode::Map(mut entries), MinifiedNode::Map(overlay)) => {
+            for (key, value) in overlay {
+                match entries.iter().position(|(k, _)| *k == key) {
+                    Some(idx) => {
+                        let existing = std::mem::replace(&mut entries[idx].1, MinifiedNode::Null);
+                        path.push(key);
+                        entries[idx].1 = merge_at(existing, value, strategy, path)?;
+                        path.pop();
+                    }
+                    None => entries.push((key, value)),
+                }
+            }
+            Ok(MinifiedNode::Map(entries))
+        }
+        (MinifiedNode::Array(mut items), MinifiedNode::Array(more)) if strategy == MergeStrategy::ConcatArrays => {
+            items.extend(more);
+            Ok(MinifiedNode::Array(items))
+        }
+        (base, overlay) if strategy == MergeStrategy::Error && base != overlay => {
+            Err(ConvertError::MergeConflict { key: path.join("/") })
+        }
+        (_, overlay) => Ok(overlay),
+    }
+}
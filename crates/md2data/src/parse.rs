@@ -1,16 +1,17 @@
 use pulldown_cmark::{Event, Parser, Tag, TagEnd };
 use crate::ast::Node;
 use crate::ast::NodeOrString;
+use crate::directives::apply_directives;
 
 /// Takes markdown text and outputs an AST
 /// 
 /// Uses pulldown_cmark to build AST, see ast.rs for structure.
 pub fn parse_markdown(input: &str) -> Node {
-    let parser = Parser::new(input);
+    let events = apply_directives(Parser::new(input).into_offset_iter().collect(), false);
     let mut stack: Vec<Node> = vec![Node::Document { children: vec![] }];
     let mut text_buf = String::new();
 
-    for ev in parser {
+    for (ev, _) in events {
         match ev {
             Event::Start(tag) => {
                 flush_text(&mut stack, &mut text_buf);
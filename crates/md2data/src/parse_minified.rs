@@ -1,11 +1,176 @@
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::path::PathBuf;
 use pulldown_cmark::{Event, Parser, Tag, TagEnd, CodeBlockKind};
 use crate::ast::MinifiedNode;
+use crate::ConvertError;
+use crate::delimited::{parse_records, CsvOptions};
+use crate::directives::{apply_directives, html_block, parse_directive, Directive, TypeHint};
+use crate::includes::IncludeOptions;
+use crate::records::{parse_front_matter, split_records};
+use crate::sanitizers::{transform_key, CollisionPolicy, KeyCase};
+
+type EventIter<'a> = std::iter::Peekable<std::vec::IntoIter<(Event<'a>, Range<usize>)>>;
+
+/// How paragraph and list item text is rendered into leaf strings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LeafFormat {
+    /// Text only, inline markup is dropped: `[docs](https://x)` -> `docs`
+    #[default]
+    Plain,
+    /// The original Markdown source: `[docs](https://x)`
+    Markdown,
+    /// Rendered HTML: `<a href="https://x">docs</a>`
+    Html,
+}
+
+/// How the content under a single heading becomes its value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SectionFormat {
+    /// Paragraphs, lists and code blocks are joined into one string,
+    /// unless the section is a single list
+    #[default]
+    Joined,
+    /// A section with several blocks becomes an array of them,
+    /// a section with a single block keeps that block's value
+    Blocks,
+}
+
+/// How fenced and indented code blocks are represented
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CodeBlockFormat {
+    /// A string that keeps the fences: "```sh\nmake\n```\n"
+    #[default]
+    Fenced,
+    /// A map with the info string and the body: `{ "lang": "sh", "code": "make\n" }`
+    Object,
+    /// The body only: "make\n"
+    Raw,
+}
+
+/// How a list item with both text and a nested list is represented
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListItemFormat {
+    /// Text and nested list become siblings: `"Authors", ["Bar1", "Bar2"]`
+    #[default]
+    Flatten,
+    /// The text becomes the key: `{ "Authors": ["Bar1", "Bar2"] }`
+    Keyed,
+    /// Explicit fields: `{ "text": "Authors", "children": ["Bar1", "Bar2"] }`
+    TextChildren,
+}
+
+/// How ordered (numbered) lists are represented
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OrderedListFormat {
+    /// A plain array, same as bullet lists
+    #[default]
+    Array,
+    /// A wrapper object: `{ "ordered": true, "start": 1, "items": [...] }`
+    Object,
+}
+
+/// What a heading with no content underneath becomes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptySection {
+    /// `""`
+    #[default]
+    EmptyString,
+    /// `null` (an empty element in XML; the key is left out in TOML, which has no null)
+    Null,
+    /// `{}`
+    EmptyMap,
+    /// The key is left out
+    Omit,
+}
+
+/// What to do with a document that has a single top-level heading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TitleMode {
+    /// The heading stays the only root key: `{ "My Doc": { "Intro": ... } }`
+    #[default]
+    Keep,
+    /// The heading moves to a `title` field beside its children: `{ "title": "My Doc", "Intro": ... }`
+    Hoist,
+    /// The heading is dropped and its children become the root keys: `{ "Intro": ... }`
+    Promote,
+}
+
+/// Options for [`parse_markdown_minified_with`]
+#[derive(Debug, Clone, Default)]
+pub struct MinifiedOptions {
+    pub leaf_format: LeafFormat,
+    pub section_format: SectionFormat,
+    pub code_format: CodeBlockFormat,
+    pub list_items: ListItemFormat,
+    pub ordered_lists: OrderedListFormat,
+    pub empty_sections: EmptySection,
+    /// Parse ```json, ```yaml and ```toml blocks and use the data as the section's value
+    pub embedded_data: bool,
+    /// Parse ```csv and ```tsv blocks into arrays of records keyed by the header row
+    pub csv: Option<CsvOptions>,
+    /// Only the top N heading levels become keys, deeper headings and
+    /// their content are folded into the parent's value as Markdown text
+    pub max_depth: Option<NonZeroUsize>,
+    pub title_mode: TitleMode,
+    /// Treat top-level thematic breaks and repeated front matter blocks as
+    /// record separators, the result is an array with one map per record
+    pub split_on_break: bool,
+    /// Resolve `<!-- md2data:include path -->` and `{{#include path}}`,
+    /// without this the directives are ignored
+    pub includes: Option<IncludeOptions>,
+    /// Rewrite heading keys into a case style. Keys from embedded data, CSV headers,
+    /// front matter and the fields the parser adds (`items`, `lang`, ...) are kept as written
+    pub key_case: Option<KeyCase>,
+    /// What to do when two keys in a map end up identical after rewriting, by
+    /// `key_case` or by the key rules of the output format
+    pub key_collisions: CollisionPolicy,
+}
 
 /// Takes markdown text and converts to an MinifiedNode AST
 /// 
 /// This is a completely different type of parsing compared to parse.rs
+///
+/// Values that don't match their `<!-- type: ... -->` hint are left as they are,
+/// use [`parse_markdown_minified_with`] to get a diagnostic instead.
 pub fn parse_markdown_minified(input: &str) -> MinifiedNode {
-    let parser = Parser::new(input);
+    match parse_minified(input, &MinifiedOptions::default(), false, &[]) {
+        Ok(node) => node,
+        Err(_) => unreachable!("default options never fail"),
+    }
+}
+
+/// Same as [`parse_markdown_minified`], with options
+///
+/// Fails when an embedded block does not parse, a value cannot be
+/// coerced to its type hint, or an included file cannot be read.
+pub fn parse_markdown_minified_with(input: &str, opts: &MinifiedOptions) -> Result<MinifiedNode, ConvertError> {
+    let chain = opts.includes.as_ref().map(IncludeOptions::chain).unwrap_or_default();
+    if !opts.split_on_break {
+        return parse_minified(input, opts, true, &chain);
+    }
+
+    let mut records = vec![];
+    for record in split_records(input) {
+        let mut entries = match record.front_matter {
+            Some(text) => parse_front_matter(text)?,
+            None => vec![],
+        };
+        if let MinifiedNode::Map(body) = parse_minified(record.body, opts, true, &chain)? {
+            entries.extend(body);
+        }
+        records.push(MinifiedNode::Map(entries));
+    }
+    Ok(MinifiedNode::Array(records))
+}
+
+fn parse_minified(
+    input: &str,
+    opts: &MinifiedOptions,
+    strict_hints: bool,
+    chain: &[PathBuf],
+) -> Result<MinifiedNode, ConvertError> {
+    let parser = Parser::new(input).into_offset_iter();
     
     // Root is always a Map
     let mut root = MinifiedNode::Map(vec![]);
@@ -13,28 +178,56 @@ pub fn parse_markdown_minified(input: &str) -> MinifiedNode {
     
     // Buffer for capturing text content
     let mut text_buf = String::new();
+    let mut leaf = LeafBuf::default();
     let mut pending_heading_level: Option<u8> = None;
+    let mut folding_heading = false;
     
     // Temporary storage for content under the current key
-    let mut current_content: Vec<MinifiedNode> = vec![];
+    let mut current_content: Vec<Block> = vec![];
+    let mut code_lang: Option<String> = None;
+
+    // Every heading path, in document order, for the empty section pass
+    let mut sections: Vec<Vec<String>> = vec![];
+    // `<!-- type: ... -->` hints and the heading path they follow
+    let mut type_hints: Vec<(Vec<String>, TypeHint)> = vec![];
+    let mut heading_keys = HeadingKeys::default();
+    // The file spliced into the current section, which then can't have text of its own
+    let mut included: Option<String> = None;
 
-    let events: Vec<Event> = parser.collect();
+    let events = apply_directives(parser.collect(), true);
     let mut iter = events.into_iter().peekable();
 
-    while let Some(ev) = iter.next() {
+    while let Some((ev, range)) = iter.next() {
+        leaf.push(&ev, range);
+
         match ev {
             // Headings become keys
             Event::Start(Tag::Heading { level, .. }) => {
+                let level = level as u8;
+
+                // Headings past max_depth stay part of the current content
+                let depth = heading_stack.iter().filter(|(l, _)| *l < level).count() + 1;
+                folding_heading = opts.max_depth.is_some_and(|max| depth > max.get());
+
                 // 1. Flush previous content to the current tip before switching keys
-                if !heading_stack.is_empty() && !current_content.is_empty() {
-                    append_content_to_tip(&mut root, &heading_stack, std::mem::take(&mut current_content));
+                if !folding_heading && !heading_stack.is_empty() && !current_content.is_empty() {
+                    if let Some(target) = &included {
+                        return Err(mixed_include(target, &heading_stack));
+                    }
+                    append_content_to_tip(&mut root, &heading_stack, std::mem::take(&mut current_content), opts);
                 }
                 
-                pending_heading_level = Some(level as u8);
+                pending_heading_level = Some(level);
                 text_buf.clear();
+                leaf.clear();
             }
             Event::End(TagEnd::Heading { .. }) => {
-                if let Some(level) = pending_heading_level {
+                if let Some(level) = pending_heading_level
+                    && folding_heading {
+                    let hashes = "#".repeat(level as usize);
+                    let title = leaf.render(&text_buf, input, opts.leaf_format);
+                    current_content.push(Block::Text(format!("{hashes} {title}")));
+                } else if let Some(level) = pending_heading_level {
                     let key = text_buf.trim().to_string();
                     
                     // Pop stack if we are going back up a level
@@ -50,9 +243,12 @@ pub fn parse_markdown_minified(input: &str) -> MinifiedNode {
                     ensure_path_is_map(&mut root, &heading_stack);
                     
                     // Add the new key (initially empty)
+                    let key = heading_keys.key(key, &heading_stack, opts)?;
                     add_key_to_map(&mut root, &heading_stack, key.clone());
                     
                     heading_stack.push((level, key));
+                    included = None;
+                    sections.push(heading_stack.iter().map(|(_, k)| k.clone()).collect());
                 }
                 pending_heading_level = None;
                 text_buf.clear();
@@ -64,14 +260,20 @@ pub fn parse_markdown_minified(input: &str) -> MinifiedNode {
                     CodeBlockKind::Fenced(l) => l.to_string(),
                     CodeBlockKind::Indented => "".to_string(),
                 };
-                text_buf.push_str("```");
-                text_buf.push_str(&lang);
-                text_buf.push('\n');
+                code_lang = Some(lang);
+                text_buf.clear();
             }
             Event::End(TagEnd::CodeBlock) => {
-                text_buf.push_str("```\n");
-                current_content.push(MinifiedNode::String(text_buf.clone()));
-                text_buf.clear();
+                let lang = code_lang.take().unwrap_or_default();
+                let code = std::mem::take(&mut text_buf);
+
+                let key = heading_stack.last().map(|(_, k)| k.as_str()).unwrap_or_default();
+                let data = parse_embedded_data(&lang, &code, key, opts)?;
+
+                match data {
+                    Some(node) => current_content.push(Block::Data(node)),
+                    None => current_content.push(Block::Code { lang, code }),
+                }
             }
 
             // Text
@@ -81,18 +283,50 @@ pub fn parse_markdown_minified(input: &str) -> MinifiedNode {
             Event::SoftBreak | Event::HardBreak => {
                 text_buf.push('\n');
             }
+            Event::Start(Tag::Paragraph) => {
+                leaf.clear();
+            }
             Event::End(TagEnd::Paragraph) => {
-                let val = text_buf.trim().to_string();
-                if !val.is_empty() {
-                    current_content.push(MinifiedNode::String(val));
+                let mdbook_include = text_buf.trim()
+                    .strip_prefix("{{#include ")
+                    .and_then(|s| s.strip_suffix("}}"))
+                    .filter(|_| opts.includes.is_some());
+
+                let val = leaf.render(&text_buf, input, opts.leaf_format);
+                if let Some(target) = mdbook_include {
+                    let target = target.trim();
+                    if !heading_stack.is_empty() && !current_content.is_empty() {
+                        return Err(mixed_include(target, &heading_stack));
+                    }
+                    include_file(target, &mut root, &heading_stack, opts, strict_hints, chain)?;
+                    included = Some(target.to_string());
+                } else if !val.is_empty() {
+                    current_content.push(Block::Text(val));
                 }
                 text_buf.clear();
+                leaf.clear();
             }
 
+            // Type hints apply to the heading they follow, includes are spliced in under it.
+            // The block is joined first, pulldown-cmark emits one event per line
+            Event::Start(Tag::HtmlBlock) => match parse_directive(&html_block(&mut iter)) {
+                Some(Directive::Type(hint)) if !heading_stack.is_empty() => {
+                    type_hints.push((heading_stack.iter().map(|(_, k)| k.clone()).collect(), hint));
+                }
+                Some(Directive::Include(target)) => {
+                    if !heading_stack.is_empty() && !current_content.is_empty() {
+                        return Err(mixed_include(&target, &heading_stack));
+                    }
+                    include_file(&target, &mut root, &heading_stack, opts, strict_hints, chain)?;
+                    included = Some(target);
+                }
+                _ => {}
+            },
+
             // Lists
-            Event::Start(Tag::List(_)) => {
-                let list_node = parse_recursive_list(&mut iter);
-                current_content.push(list_node);
+            Event::Start(Tag::List(start)) => {
+                let list = parse_recursive_list(&mut iter, start, input, opts);
+                current_content.push(Block::List(list));
             }
 
             _ => {}
@@ -101,41 +335,269 @@ pub fn parse_markdown_minified(input: &str) -> MinifiedNode {
 
     // Final Flush
     if !heading_stack.is_empty() && !current_content.is_empty() {
-        append_content_to_tip(&mut root, &heading_stack, current_content);
+        if let Some(target) = &included {
+            return Err(mixed_include(target, &heading_stack));
+        }
+        append_content_to_tip(&mut root, &heading_stack, current_content, opts);
+    }
+
+    for (path, hint) in type_hints {
+        if let Some((map, idx)) = find_section(&mut root, &path) {
+            match hint.coerce(map[idx].1.clone()) {
+                Ok(value) => map[idx].1 = value,
+                Err(message) if strict_hints => {
+                    return Err(ConvertError::TypeHint {
+                        key: path.join("/"),
+                        hint: hint.to_string(),
+                        message,
+                    });
+                }
+                Err(_) => {}
+            }
+        }
+    }
+
+    if opts.empty_sections != EmptySection::EmptyString {
+        // Deepest and latest first, so omitted keys never shift a path still to visit
+        for path in sections.iter().rev() {
+            apply_empty_section(&mut root, path, opts.empty_sections);
+        }
+    }
+
+    apply_title_mode(root, opts.title_mode, &heading_keys)
+}
+
+/// Parses an included file with the same options and adds its keys under the current heading
+///
+/// The keys would replace any text the heading had, so the caller rejects sections with both.
+fn include_file(
+    target: &str,
+    root: &mut MinifiedNode,
+    path: &[(u8, String)],
+    opts: &MinifiedOptions,
+    strict_hints: bool,
+    chain: &[PathBuf],
+) -> Result<(), ConvertError> {
+    let Some(includes) = &opts.includes else { return Ok(()) };
+    let (file, text) = includes.resolve(target, chain)?;
+
+    let child_opts = MinifiedOptions {
+        includes: Some(IncludeOptions { root: includes.root.clone(), origin: Some(file.clone()) }),
+        ..opts.clone()
+    };
+    let mut child_chain = chain.to_vec();
+    child_chain.push(file);
+
+    let MinifiedNode::Map(entries) = parse_minified(&text, &child_opts, strict_hints, &child_chain)? else {
+        return Ok(());
+    };
+
+    ensure_path_is_map(root, path);
+    let mut current = root;
+    for (_, segment) in path {
+        let MinifiedNode::Map(map) = current else { unreachable!() };
+        let idx = map.iter().position(|(k, _)| k == segment).expect("Path broken in include_file");
+        current = &mut map[idx].1;
+    }
+    if let MinifiedNode::Map(map) = current {
+        map.extend(entries);
+    }
+    Ok(())
+}
+
+/// A section can hold included keys or text, not both, since one would replace the other
+fn mixed_include(target: &str, path: &[(u8, String)]) -> ConvertError {
+    let key = path.iter().map(|(_, k)| k.as_str()).collect::<Vec<_>>().join("/");
+    ConvertError::Include {
+        path: target.to_string(),
+        message: format!("\"{key}\" also has text, move it to a subsection or into the included file"),
+    }
+}
+
+/// Rewrites heading keys with `key_case`, remembering the heading each key came from
+#[derive(Default)]
+struct HeadingKeys {
+    /// Parent path, key, and the heading as written
+    keys: Vec<(Vec<String>, String, String)>,
+}
+
+impl HeadingKeys {
+    fn key(&mut self, heading: String, parent: &[(u8, String)], opts: &MinifiedOptions) -> Result<String, ConvertError> {
+        let Some(case) = opts.key_case else { return Ok(heading) };
+        let parent: Vec<String> = parent.iter().map(|(_, k)| k.clone()).collect();
+
+        let base = transform_key(&heading, case);
+        let mut key = base.clone();
+        let mut n = 2;
+        // A repeated heading keeps its key, different headings that end up equal collide
+        while let Some((_, _, first)) = self.keys.iter().find(|(p, k, _)| *p == parent && *k == key) {
+            if *first == heading {
+                return Ok(key);
+            }
+            match opts.key_collisions {
+                CollisionPolicy::Error => {
+                    return Err(ConvertError::KeyCollision { key, first: first.clone(), second: heading });
+                }
+                CollisionPolicy::Disambiguate => {
+                    key = format!("{base}_{n}");
+                    n += 1;
+                }
+            }
+        }
+        self.keys.push((parent, key.clone(), heading));
+        Ok(key)
+    }
+
+    /// The heading a top-level key was written as
+    fn original(&self, key: &str) -> Option<&str> {
+        self.keys.iter()
+            .find(|(p, k, _)| p.is_empty() && k == key)
+            .map(|(_, _, heading)| heading.as_str())
+    }
+}
+
+/// Hoists or promotes the children of a single top-level heading
+///
+/// Only applies when that heading has subsections, otherwise its content would be lost.
+fn apply_title_mode(root: MinifiedNode, mode: TitleMode, heading_keys: &HeadingKeys) -> Result<MinifiedNode, ConvertError> {
+    if mode == TitleMode::Keep {
+        return Ok(root);
+    }
+
+    let MinifiedNode::Map(mut entries) = root else { return Ok(root) };
+    if entries.len() != 1 || !matches!(entries[0].1, MinifiedNode::Map(_)) {
+        return Ok(MinifiedNode::Map(entries));
+    }
+
+    let (title, children) = entries.remove(0);
+    let MinifiedNode::Map(children) = children else { unreachable!() };
+
+    match mode {
+        TitleMode::Promote => Ok(MinifiedNode::Map(children)),
+        TitleMode::Hoist => {
+            if children.iter().any(|(k, _)| k == "title") {
+                return Err(ConvertError::KeyCollision {
+                    key: "title".to_string(),
+                    first: title,
+                    second: "title".to_string(),
+                });
+            }
+            // The title is content, so it keeps the heading as written
+            let text = heading_keys.original(&title).unwrap_or(&title).to_string();
+            let mut hoisted = vec![("title".to_string(), MinifiedNode::String(text))];
+            hoisted.extend(children);
+            Ok(MinifiedNode::Map(hoisted))
+        }
+        TitleMode::Keep => unreachable!(),
+    }
+}
+
+/// Replaces the value at `path` according to `policy` if its heading had no content
+fn apply_empty_section(root: &mut MinifiedNode, path: &[String], policy: EmptySection) {
+    let Some((map, idx)) = find_section(root, path) else { return };
+
+    if !matches!(&map[idx].1, MinifiedNode::String(s) if s.is_empty()) {
+        return;
+    }
+    match policy {
+        EmptySection::EmptyString => {}
+        EmptySection::Null => map[idx].1 = MinifiedNode::Null,
+        EmptySection::EmptyMap => map[idx].1 = MinifiedNode::Map(vec![]),
+        EmptySection::Omit => { map.remove(idx); }
+    }
+}
+
+/// Finds the map holding the section at `path`, and the section's index in it
+fn find_section<'a>(root: &'a mut MinifiedNode, path: &[String]) -> Option<(&'a mut Vec<(String, MinifiedNode)>, usize)> {
+    let (key, parent_path) = path.split_last()?;
+
+    let mut current = root;
+    for segment in parent_path {
+        match current {
+            MinifiedNode::Map(map) => {
+                let idx = map.iter().position(|(k, _)| k == segment)?;
+                current = &mut map[idx].1;
+            }
+            _ => return None,
+        }
     }
 
-    root
+    let MinifiedNode::Map(map) = current else { return None };
+    let idx = map.iter().position(|(k, _)| k == key)?;
+    Some((map, idx))
 }
 
-fn parse_recursive_list(iter: &mut std::iter::Peekable<std::vec::IntoIter<Event>>) -> MinifiedNode {
+/// Parses the body of a ```json, ```yaml, ```toml, ```csv or ```tsv block
+///
+/// Returns `None` for any other language, one whose option is off,
+/// or one whose format feature is disabled.
+fn parse_embedded_data(
+    info: &str,
+    code: &str,
+    key: &str,
+    opts: &MinifiedOptions,
+) -> Result<Option<MinifiedNode>, ConvertError> {
+    let lang = info.split_whitespace().next().unwrap_or_default().to_ascii_lowercase();
+
+    let parsed: Result<MinifiedNode, String> = match (lang.as_str(), &opts.csv) {
+        ("csv", Some(csv)) => parse_records(code, csv.delimiter.unwrap_or(','), csv.infer_types),
+        ("tsv", Some(csv)) => parse_records(code, csv.delimiter.unwrap_or('\t'), csv.infer_types),
+
+        _ if !opts.embedded_data => return Ok(None),
+
+        ("json", _) => serde_json::from_str(code).map_err(|e| e.to_string()),
+
+        #[cfg(feature = "yaml")]
+        ("yaml" | "yml", _) => serde_yaml::from_str(code).map_err(|e| e.to_string()),
+
+        #[cfg(feature = "toml")]
+        ("toml", _) => toml::from_str(code).map_err(|e| e.to_string()),
+
+        _ => return Ok(None),
+    };
+
+    parsed.map(Some).map_err(|message| ConvertError::EmbeddedData {
+        key: key.to_string(),
+        lang,
+        message: message.trim().to_string(),
+    })
+}
+
+fn parse_recursive_list(iter: &mut EventIter, start: Option<u64>, input: &str, opts: &MinifiedOptions) -> List {
     let mut items = vec![];
     let mut text_buf = String::new();
+    let mut leaf = LeafBuf::default();
 
-    while let Some(ev) = iter.next() {
+    while let Some((ev, _)) = iter.next() {
         match ev {
             Event::End(TagEnd::List(_)) => break,
             
             Event::Start(Tag::Item) => {
                 text_buf.clear();
+                leaf.clear();
                 
-                let mut item_parts: Vec<MinifiedNode> = vec![];
+                let mut item_parts: Vec<ItemPart> = vec![];
+
+                while let Some((sub_ev, range)) = iter.peek() {
+                    leaf.push(sub_ev, range.clone());
 
-                while let Some(sub_ev) = iter.peek() {
                     match sub_ev {
                         Event::End(TagEnd::Item) => { 
                             iter.next(); 
                             break; 
                         },
-                        Event::Start(Tag::List(_)) => {
-                            let txt = text_buf.trim().to_string();
+                        Event::Start(Tag::List(nested_start)) => {
+                            let nested_start = *nested_start;
+                            let txt = leaf.render(&text_buf, input, opts.leaf_format);
                             if !txt.is_empty() {
-                                item_parts.push(MinifiedNode::String(txt));
-                                text_buf.clear();
+                                item_parts.push(ItemPart::Text(txt));
                             }
+                            text_buf.clear();
+                            leaf.clear();
                             
                             iter.next(); 
-                            let nested = parse_recursive_list(iter);
-                            item_parts.push(nested);
+                            let nested = parse_recursive_list(iter, nested_start, input, opts);
+                            item_parts.push(ItemPart::List(nested));
                         }
                         Event::Text(t) | Event::Code(t) => {
                             text_buf.push_str(t);
@@ -149,26 +611,213 @@ fn parse_recursive_list(iter: &mut std::iter::Peekable<std::vec::IntoIter<Event>
                     }
                 }
 
-                let txt = text_buf.trim().to_string();
+                let txt = leaf.render(&text_buf, input, opts.leaf_format);
                 if !txt.is_empty() {
-                    item_parts.push(MinifiedNode::String(txt));
+                    item_parts.push(ItemPart::Text(txt));
                 }
 
-                if item_parts.len() == 1 {
-                    items.push(item_parts[0].clone());
-                } else {
-                    for part in item_parts {
-                        items.push(part);
+                items.push(item_parts);
+            }
+            _ => {}
+        }
+    }
+    List { start, items }
+}
+
+/// A parsed list, kept apart from `MinifiedNode` until the section is
+/// resolved so it can be converted or re-stringified without losing detail
+struct List {
+    /// First number of an ordered list, `None` for bullets
+    start: Option<u64>,
+    items: Vec<Vec<ItemPart>>,
+}
+
+enum ItemPart {
+    Text(String),
+    List(List),
+}
+
+impl List {
+    fn into_node(self, opts: &MinifiedOptions) -> MinifiedNode {
+        let mut out = vec![];
+
+        for parts in self.items {
+            let has_text = parts.iter().any(|p| matches!(p, ItemPart::Text(_)));
+            let has_list = parts.iter().any(|p| matches!(p, ItemPart::List(_)));
+
+            if has_text && has_list && opts.list_items != ListItemFormat::Flatten {
+                let mut texts = vec![];
+                let mut lists = vec![];
+                for part in parts {
+                    match part {
+                        ItemPart::Text(t) => texts.push(t),
+                        ItemPart::List(l) => lists.push(l.into_node(opts)),
                     }
                 }
+
+                let text = texts.join(" ");
+                let children = if lists.len() == 1 {
+                    lists.remove(0)
+                } else {
+                    MinifiedNode::Array(lists)
+                };
+
+                out.push(match opts.list_items {
+                    ListItemFormat::Keyed => MinifiedNode::Map(vec![(text, children)]),
+                    _ => MinifiedNode::Map(vec![
+                        ("text".to_string(), MinifiedNode::String(text)),
+                        ("children".to_string(), children),
+                    ]),
+                });
+                continue;
             }
-            _ => {}
+
+            // Flatten: text and nested lists become siblings
+            for part in parts {
+                out.push(match part {
+                    ItemPart::Text(t) => MinifiedNode::String(t),
+                    ItemPart::List(l) => l.into_node(opts),
+                });
+            }
+        }
+
+        match (self.start, opts.ordered_lists) {
+            (Some(start), OrderedListFormat::Object) => MinifiedNode::Map(vec![
+                ("ordered".to_string(), MinifiedNode::Bool(true)),
+                ("start".to_string(), MinifiedNode::Integer(start as i64)),
+                ("items".to_string(), MinifiedNode::Array(out)),
+            ]),
+            _ => MinifiedNode::Array(out),
+        }
+    }
+
+    /// Re-emits the list as Markdown, keeping the numbering of ordered lists
+    fn stringify(&self, buf: &mut String, indent: usize) {
+        let spaces = " ".repeat(indent);
+        for (i, parts) in self.items.iter().enumerate() {
+            let marker = match self.start {
+                Some(start) => format!("{}. ", start + i as u64),
+                None => "* ".to_string(),
+            };
+            for part in parts {
+                match part {
+                    ItemPart::Text(s) => buf.push_str(&format!("{}{}{}\n", spaces, marker, s)),
+                    // Nested lists line up with the item text
+                    ItemPart::List(l) => l.stringify(buf, indent + marker.len()),
+                }
+            }
+        }
+    }
+}
+
+/// A block-level piece of content collected under the current key
+enum Block {
+    Text(String),
+    List(List),
+    Code { lang: String, code: String },
+    Data(MinifiedNode),
+}
+
+impl Block {
+    /// The block as a standalone value
+    fn into_node(self, opts: &MinifiedOptions) -> MinifiedNode {
+        match self {
+            Block::Text(s) => MinifiedNode::String(s),
+            Block::List(list) => list.into_node(opts),
+            Block::Data(data) => data,
+            Block::Code { lang, code } => match opts.code_format {
+                CodeBlockFormat::Fenced => MinifiedNode::String(fenced(&lang, &code)),
+                CodeBlockFormat::Raw => MinifiedNode::String(code),
+                CodeBlockFormat::Object => MinifiedNode::Map(vec![
+                    ("lang".to_string(), MinifiedNode::String(lang)),
+                    ("code".to_string(), MinifiedNode::String(code)),
+                ]),
+            },
+        }
+    }
+
+    /// Appends the block to a joined section string
+    fn stringify(&self, buf: &mut String) {
+        match self {
+            Block::Text(s) => {
+                buf.push_str(s);
+                buf.push_str("\n\n");
+            }
+            Block::Code { lang, code } => {
+                buf.push_str(&fenced(lang, code));
+                buf.push_str("\n\n");
+            }
+            Block::List(list) => {
+                list.stringify(buf, 0);
+                buf.push('\n');
+            }
+            Block::Data(_) => {}
         }
     }
-    MinifiedNode::Array(items)
 }
 
-/// --- Helper logic ---
+fn fenced(lang: &str, code: &str) -> String {
+    format!("```{lang}\n{code}```\n")
+}
+
+/// Collects the inline events of a paragraph or list item so the leaf
+/// can be rendered as source Markdown or HTML instead of plain text
+#[derive(Default)]
+struct LeafBuf<'a> {
+    events: Vec<Event<'a>>,
+    span: Option<Range<usize>>,
+}
+
+impl<'a> LeafBuf<'a> {
+    fn push(&mut self, ev: &Event<'a>, range: Range<usize>) {
+        if !is_inline(ev) {
+            return;
+        }
+        self.events.push(ev.clone());
+        self.span = Some(match self.span.take() {
+            Some(span) => span.start.min(range.start)..span.end.max(range.end),
+            None => range,
+        });
+    }
+
+    fn clear(&mut self) {
+        self.events.clear();
+        self.span = None;
+    }
+
+    /// `plain` is the text-only rendering the caller accumulated itself
+    fn render(&self, plain: &str, input: &str, format: LeafFormat) -> String {
+        match format {
+            LeafFormat::Plain => plain.trim().to_string(),
+            LeafFormat::Markdown => match &self.span {
+                Some(span) if !span.is_empty() => input[span.clone()].trim().to_string(),
+                // Text with no source of its own, like a key override, is written as is
+                _ => plain.trim().to_string(),
+            },
+            LeafFormat::Html => {
+                let mut html = String::new();
+                pulldown_cmark::html::push_html(&mut html, self.events.iter().cloned());
+                html.trim().to_string()
+            }
+        }
+    }
+}
+
+fn is_inline(ev: &Event) -> bool {
+    match ev {
+        Event::Text(_) | Event::Code(_) | Event::InlineHtml(_) | Event::InlineMath(_)
+        | Event::SoftBreak | Event::HardBreak | Event::FootnoteReference(_) => true,
+        Event::Start(tag) => matches!(tag,
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Superscript
+            | Tag::Subscript | Tag::Link { .. } | Tag::Image { .. }),
+        Event::End(tag) => matches!(tag,
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Superscript
+            | TagEnd::Subscript | TagEnd::Link | TagEnd::Image),
+        _ => false,
+    }
+}
+
+// --- Helper logic ---
 
 fn ensure_path_is_map(root: &mut MinifiedNode, path: &[(u8, String)]) {
     let mut current = root;
@@ -221,7 +870,7 @@ fn add_key_to_map(root: &mut MinifiedNode, path: &[(u8, String)], key: String) {
     }
 }
 
-fn append_content_to_tip(root: &mut MinifiedNode, path: &[(u8, String)], content: Vec<MinifiedNode>) {
+fn append_content_to_tip(root: &mut MinifiedNode, path: &[(u8, String)], content: Vec<Block>, opts: &MinifiedOptions) {
     if path.is_empty() { return; }
     
     let (_key_lvl, key_str) = path.last().unwrap();
@@ -239,64 +888,57 @@ fn append_content_to_tip(root: &mut MinifiedNode, path: &[(u8, String)], content
         }
     }
 
-    if let MinifiedNode::Map(map) = current {
-        if let Some(idx) = map.iter().position(|(k, _)| k == key_str) {
-            let target = &mut map[idx].1;
-            
-            // Conflict Rule: Subheadings win. Content ignored/clobbered.
-            if matches!(target, MinifiedNode::Map(_)) {
-                return;
-            }
+    if let MinifiedNode::Map(map) = current
+        && let Some(idx) = map.iter().position(|(k, _)| k == key_str) {
+        let target = &mut map[idx].1;
+        
+        // Conflict Rule: Subheadings win. Content ignored/clobbered.
+        if matches!(target, MinifiedNode::Map(_)) {
+            return;
+        }
+
+        // Embedded data and unfenced code blocks are kept apart from the text around them
+        let keep_apart = content.iter().any(|b| match b {
+            Block::Data(_) => true,
+            Block::Code { .. } => opts.code_format != CodeBlockFormat::Fenced,
+            _ => false,
+        });
 
-            // Heuristic: Pure List vs Mixed Content
-            let has_text = content.iter().any(|n| matches!(n, MinifiedNode::String(_)));
-            let has_list = content.iter().any(|n| matches!(n, MinifiedNode::Array(_)));
+        if opts.section_format == SectionFormat::Blocks || keep_apart {
+            let mut values: Vec<MinifiedNode> = vec![];
+            if !matches!(target, MinifiedNode::String(s) if s.is_empty()) {
+                values.push(std::mem::replace(target, MinifiedNode::Array(vec![])));
+            }
+            values.extend(content.into_iter().map(|b| b.into_node(opts)));
 
-            if !has_text && has_list && content.len() == 1 {
-                *target = content[0].clone();
+            *target = if values.len() == 1 {
+                values.remove(0)
             } else {
-                let mut combined_string = String::new();
-                
-                if let MinifiedNode::String(s) = target {
-                    if !s.is_empty() {
-                        combined_string.push_str(s);
-                        combined_string.push_str("\n\n");
-                    }
-                }
+                MinifiedNode::Array(values)
+            };
+            return;
+        }
 
-                for node in content {
-                    match node {
-                        MinifiedNode::String(s) => {
-                            combined_string.push_str(&s);
-                            combined_string.push_str("\n\n");
-                        }
-                        MinifiedNode::Array(arr) => {
-                            for item in arr {
-                                stringify_list_item(&mut combined_string, &item, 0);
-                            }
-                            combined_string.push('\n');
-                        }
-                        _ => {}
-                    }
-                }
-                
-                *target = MinifiedNode::String(combined_string.trim().to_string());
+        // Heuristic: Pure List vs Mixed Content
+        let has_text = content.iter().any(|b| !matches!(b, Block::List(_)));
+        let has_list = content.iter().any(|b| matches!(b, Block::List(_)));
+
+        if !has_text && has_list && content.len() == 1 {
+            *target = content.into_iter().next().unwrap().into_node(opts);
+        } else {
+            let mut combined_string = String::new();
+            
+            if let MinifiedNode::String(s) = target
+                && !s.is_empty() {
+                combined_string.push_str(s);
+                combined_string.push_str("\n\n");
             }
-        }
-    }
-}
 
-fn stringify_list_item(buf: &mut String, node: &MinifiedNode, indent: usize) {
-    let spaces = " ".repeat(indent);
-    match node {
-        MinifiedNode::String(s) => {
-            buf.push_str(&format!("{}* {}\n", spaces, s));
-        }
-        MinifiedNode::Array(arr) => {
-            for sub in arr {
-                stringify_list_item(buf, sub, indent + 2);
+            for block in &content {
+                block.stringify(&mut combined_string);
             }
+            
+            *target = MinifiedNode::String(combined_string.trim().to_string());
         }
-        _ => {}
     }
-}
\ No newline at end of file
+}
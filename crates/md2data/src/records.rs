@@ -0,0 +1,162 @@
+use std::ops::Range;
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use crate::ast::MinifiedNode;
+use crate::ConvertError;
+
+/// One record of a multi-document file
+pub(crate) struct Record<'a> {
+    pub front_matter: Option<&'a str>,
+    pub body: &'a str,
+}
+
+/// Splits `input` into records at top-level thematic breaks (`***`, `---`, `___`)
+/// and at front matter blocks, which each start a new record
+///
+/// Records with neither front matter nor content are dropped.
+pub(crate) fn split_records(input: &str) -> Vec<Record<'_>> {
+    let mut records = vec![];
+
+    for (front_matter, segment) in split_front_matter(input) {
+        for (i, range) in split_on_rules(segment).into_iter().enumerate() {
+            let body = &segment[range];
+            // Front matter belongs to the first record after it
+            let front_matter = if i == 0 { front_matter } else { None };
+
+            if front_matter.is_some() || !body.trim().is_empty() {
+                records.push(Record { front_matter, body });
+            }
+        }
+    }
+    records
+}
+
+/// Parses a front matter block into map entries
+///
+/// YAML when the `yaml` feature is enabled, otherwise `key: value` lines.
+pub(crate) fn parse_front_matter(text: &str) -> Result<Vec<(String, MinifiedNode)>, ConvertError> {
+    #[cfg(feature = "yaml")]
+    {
+        let node: MinifiedNode = serde_yaml::from_str(text).map_err(|e| ConvertError::EmbeddedData {
+            key: "front matter".to_string(),
+            lang: "yaml".to_string(),
+            message: e.to_string(),
+        })?;
+        match node {
+            MinifiedNode::Map(entries) => Ok(entries),
+            MinifiedNode::Null => Ok(vec![]),
+            _ => Err(ConvertError::EmbeddedData {
+                key: "front matter".to_string(),
+                lang: "yaml".to_string(),
+                message: "expected a map".to_string(),
+            }),
+        }
+    }
+
+    #[cfg(not(feature = "yaml"))]
+    {
+        Ok(text.lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(k, v)| (k.trim().to_string(), MinifiedNode::String(v.trim().to_string())))
+            .collect())
+    }
+}
+
+/// Cuts out `---` delimited front matter blocks, returning each one with the text that follows it
+///
+/// The opening `---` must start the input or stand where a thematic break could
+/// (after a blank line, otherwise it underlines a setext heading), with the block
+/// straight after it. Every line up to the closing `---` must look like YAML, and at
+/// least one must be a `key: value` entry, so a record made of a heading and a list
+/// between two `---` breaks stays Markdown.
+fn split_front_matter(input: &str) -> Vec<(Option<&str>, &str)> {
+    let lines = line_spans(input);
+    let mut out = vec![];
+    let mut segment_start = 0;
+    let mut front_matter = None;
+    let mut in_fence = false;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &input[lines[i].clone()];
+        let trimmed = line.trim_end();
+
+        if trimmed.trim_start().starts_with("```") || trimmed.trim_start().starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+
+        let after_blank = i == 0 || input[lines[i - 1].clone()].trim().is_empty();
+        let block_follows = lines.get(i + 1).is_some_and(|l| !input[l.clone()].trim().is_empty());
+        if !in_fence && trimmed == "---" && after_blank && block_follows
+            && let Some(close) = find_front_matter_end(input, &lines, i + 1) {
+            out.push((front_matter, &input[segment_start..lines[i].start]));
+
+            let fm_start = lines.get(i + 1).map_or(input.len(), |l| l.start);
+            front_matter = Some(&input[fm_start..lines[close].start]);
+            segment_start = lines.get(close + 1).map_or(input.len(), |l| l.start);
+            i = close + 1;
+            continue;
+        }
+        i += 1;
+    }
+
+    out.push((front_matter, &input[segment_start..]));
+    out
+}
+
+fn find_front_matter_end(input: &str, lines: &[Range<usize>], from: usize) -> Option<usize> {
+    let mut has_key = false;
+    for (i, range) in lines.iter().enumerate().skip(from) {
+        let line = input[range.clone()].trim_end();
+        if line == "---" {
+            // Headings and lists alone are Markdown between two breaks, not front matter
+            return has_key.then_some(i);
+        }
+        let is_key = line.split_once(':').is_some_and(|(k, _)| {
+            !k.is_empty() && !k.starts_with(['-', '#']) && !k.contains(char::is_whitespace)
+        });
+        has_key |= is_key;
+        if !(is_key || line.is_empty() || line.starts_with([' ', '\t', '-', '#'])) {
+            return None;
+        }
+    }
+    None
+}
+
+/// Byte ranges of each line, including the line break
+fn line_spans(input: &str) -> Vec<Range<usize>> {
+    let mut spans = vec![];
+    let mut start = 0;
+    for (i, c) in input.char_indices() {
+        if c == '\n' {
+            spans.push(start..i + 1);
+            start = i + 1;
+        }
+    }
+    if start < input.len() {
+        spans.push(start..input.len());
+    }
+    spans
+}
+
+/// Byte ranges of the text between top-level thematic breaks
+fn split_on_rules(input: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut start = 0;
+    let mut depth = 0usize;
+
+    for (ev, range) in Parser::new(input).into_offset_iter() {
+        match ev {
+            Event::Start(Tag::HtmlBlock) | Event::End(TagEnd::HtmlBlock) => {}
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            Event::Rule if depth == 0 => {
+                ranges.push(start..range.start);
+                start = range.end;
+            }
+            _ => {}
+        }
+    }
+
+    ranges.push(start..input.len());
+    ranges
+}
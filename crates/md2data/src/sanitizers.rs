@@ -1,21 +1,118 @@
-use crate::MinifiedNode;
+use crate::{ConvertError, MinifiedNode};
+
+/// How to handle two keys in the same map that sanitize to the same value
+///
+/// Keys that were already equal, like a repeated heading, are not a collision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CollisionPolicy {
+    /// Fail with `ConvertError::KeyCollision` naming both original headings
+    #[default]
+    Error,
+    /// Append `_2`, `_3`, ... to later duplicates, in document order
+    Disambiguate,
+}
+
+/// Naming rules a key has to follow for a given output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyProfile {
+    /// XML tag names, e.g. "1st Item" -> "_1st_Item"
+    Xml,
+    /// TOML keys, quoted unless they are bare (`A-Za-z0-9_-`), e.g. "Build Settings" -> "\"Build Settings\"".
+    /// Matches the TOML output, which quotes keys the same way
+    Toml,
+    /// Shell environment variable names, e.g. "api-key" -> "API_KEY"
+    Env,
+    /// INI keys, e.g. "a = b" -> "a _ b"
+    Ini,
+}
 
 /// Recursively traverses the AST and replaces keys with valid XML tag names
+///
+/// Keys that collide after sanitization are disambiguated, see [`sanitize_keys_for`].
 pub fn sanitize_keys(node: MinifiedNode) -> MinifiedNode {
+    match rewrite_keys(node, &sanitize_xml_tag, CollisionPolicy::Disambiguate) {
+        Ok(node) => node,
+        Err(_) => unreachable!("disambiguation never fails"),
+    }
+}
+
+/// Recursively replaces keys with ones that are valid under `profile`
+/// E.g., "My Key" and "My_Key" both become "My_Key" under `KeyProfile::Xml`
+pub fn sanitize_keys_for(
+    node: MinifiedNode,
+    profile: KeyProfile,
+    policy: CollisionPolicy,
+) -> Result<MinifiedNode, ConvertError> {
+    rewrite_keys(node, &|key| sanitize_key(key, profile), policy)
+}
+
+/// Transforms a single key so it is valid under `profile`
+pub fn sanitize_key(s: &str, profile: KeyProfile) -> String {
+    match profile {
+        KeyProfile::Xml => sanitize_xml_tag(s),
+        KeyProfile::Toml => sanitize_toml_key(s),
+        KeyProfile::Env => sanitize_env_name(s),
+        KeyProfile::Ini => sanitize_ini_key(s),
+    }
+}
+
+/// Whether `s` can be written as a TOML key without quotes
+pub fn is_toml_bare_key(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(is_toml_bare_char)
+}
+
+/// Recursively applies `rename` to every map key, checking for collisions within each map
+pub(crate) fn rewrite_keys(
+    node: MinifiedNode,
+    rename: &dyn Fn(&str) -> String,
+    policy: CollisionPolicy,
+) -> Result<MinifiedNode, ConvertError> {
     match node {
         MinifiedNode::Map(entries) => {
-            let sanitized_entries = entries.into_iter().map(|(key, value)| {
-                let clean_key = sanitize_xml_tag(&key);
-                (clean_key, sanitize_keys(value))
-            }).collect();
-            MinifiedNode::Map(sanitized_entries)
+            let mut out: Vec<(String, MinifiedNode)> = Vec::with_capacity(entries.len());
+            let mut originals: Vec<String> = Vec::with_capacity(entries.len());
+
+            for (key, value) in entries {
+                let mut new_key = rename(&key);
+
+                // Repeated headings stay repeated, only keys that rewriting made equal collide
+                let clash = out.iter().zip(&originals).position(|((k, _), original)| *k == new_key && *original != key);
+                if let Some(idx) = clash {
+                    match policy {
+                        CollisionPolicy::Error => {
+                            return Err(ConvertError::KeyCollision {
+                                key: new_key,
+                                first: originals[idx].clone(),
+                                second: key,
+                            });
+                        }
+                        CollisionPolicy::Disambiguate => {
+                            let base = new_key;
+                            let mut n = 2;
+                            loop {
+                                new_key = format!("{base}_{n}");
+                                if !out.iter().any(|(k, _)| *k == new_key) {
+                                    break;
+                                }
+                                n += 1;
+                            }
+                        }
+                    }
+                }
+
+                out.push((new_key, rewrite_keys(value, rename, policy)?));
+                originals.push(key);
+            }
+            Ok(MinifiedNode::Map(out))
         },
         MinifiedNode::Array(items) => {
-            let sanitized_items = items.into_iter().map(sanitize_keys).collect();
-            MinifiedNode::Array(sanitized_items)
+            let items = items.into_iter()
+                .map(|item| rewrite_keys(item, rename, policy))
+                .collect::<Result<_, _>>()?;
+            Ok(MinifiedNode::Array(items))
         },
         // Leaves (Strings) are content, so they don't need sanitisation
-        _ => node,
+        _ => Ok(node),
     }
 }
 
@@ -51,4 +148,206 @@ fn sanitize_xml_tag(s: &str) -> String {
         return "_".to_string();
     }
     out
-}
\ No newline at end of file
+}
+
+/// Case styles for [`transform_keys`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCase {
+    /// `build_settings`
+    Snake,
+    /// `buildSettings`
+    Camel,
+    /// `build-settings`
+    Kebab,
+    /// `BUILD_SETTINGS`
+    ScreamingSnake,
+    /// `build-settings`, restricted to ASCII letters and digits
+    Slug,
+}
+
+fn is_toml_bare_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Writes a string as a TOML key, quoting it unless it is a bare key
+/// E.g., "max-retries" -> "max-retries", "Build Settings" -> "\"Build Settings\""
+fn sanitize_toml_key(s: &str) -> String {
+    if is_toml_bare_key(s) {
+        return s.to_string();
+    }
+
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Transforms a string into a portable environment variable name
+/// E.g., "api-key" -> "API_KEY", "2fa" -> "_2FA"
+fn sanitize_env_name(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+
+    for c in s.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c.to_ascii_uppercase());
+        } else {
+            out.push('_');
+        }
+    }
+
+    // Names must not start with a digit
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    out
+}
+
+/// Transforms a string into a key INI readers will accept
+/// Section brackets, separators, comment markers and line breaks become underscores
+/// E.g., "a = b" -> "a _ b", "[core]" -> "_core_"
+fn sanitize_ini_key(s: &str) -> String {
+    let out: String = s.trim()
+        .chars()
+        .map(|c| match c {
+            '=' | ':' | ';' | '#' | '[' | ']' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    if out.is_empty() {
+        return "_".to_string();
+    }
+    out
+}
+
+/// Recursively rewrites every map key into the given case style.
+///
+/// Returns `ConvertError::KeyCollision` when two keys in the same map
+/// normalize to the same value (e.g. "Build Settings" and "build_settings").
+pub fn transform_keys(node: MinifiedNode, case: KeyCase) -> Result<MinifiedNode, ConvertError> {
+    rewrite_keys(node, &|key| transform_key(key, case), CollisionPolicy::Error)
+}
+
+/// Transforms a single heading into the given case style
+/// E.g., "1.2 Build Settings:" -> "build_settings" (Snake)
+pub fn transform_key(s: &str, case: KeyCase) -> String {
+    let words = split_words(strip_numbering(strip_trailing_colons(s)));
+
+    let out = match case {
+        KeyCase::Snake => join_lower(&words, "_"),
+        KeyCase::Kebab => join_lower(&words, "-"),
+        KeyCase::ScreamingSnake => words.iter()
+            .map(|w| w.to_uppercase())
+            .collect::<Vec<_>>()
+            .join("_"),
+        KeyCase::Camel => {
+            let mut out = String::new();
+            for (i, w) in words.iter().enumerate() {
+                let lower = w.to_lowercase();
+                if i == 0 {
+                    out.push_str(&lower);
+                } else {
+                    let mut chars = lower.chars();
+                    if let Some(first) = chars.next() {
+                        out.extend(first.to_uppercase());
+                        out.push_str(chars.as_str());
+                    }
+                }
+            }
+            out
+        },
+        KeyCase::Slug => {
+            let ascii: Vec<String> = words.iter()
+                .map(|w| w.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>())
+                .filter(|w| !w.is_empty())
+                .collect();
+            join_lower(&ascii, "-")
+        },
+    };
+
+    if out.is_empty() {
+        return "_".to_string();
+    }
+    out
+}
+
+fn join_lower(words: &[String], sep: &str) -> String {
+    words.iter()
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+fn strip_trailing_colons(s: &str) -> &str {
+    s.trim().trim_end_matches(':').trim_end()
+}
+
+/// Removes leading section numbering such as "1.", "2)" or "1.2.3"
+/// A bare number ("2024 Roadmap") is kept, since it is likely part of the title
+fn strip_numbering(s: &str) -> &str {
+    let digits_end = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+
+    let mut rest = s;
+    let mut has_separator = false;
+    loop {
+        let n = digits_end(rest);
+        if n == 0 {
+            break;
+        }
+        let after = &rest[n..];
+        if let Some(next) = after.strip_prefix('.') {
+            has_separator = true;
+            rest = next;
+        } else if let Some(next) = after.strip_prefix(')') {
+            has_separator = true;
+            rest = next;
+            break;
+        } else {
+            rest = after;
+            break;
+        }
+    }
+
+    // Only strip if the numbering is followed by whitespace
+    if has_separator && rest.starts_with(char::is_whitespace) {
+        rest.trim_start()
+    } else {
+        s
+    }
+}
+
+/// Splits a heading into words on non-alphanumeric characters and camelCase humps
+fn split_words(s: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut current = String::new();
+    let mut prev_lower = false;
+
+    for c in s.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.push(c);
+    }
+
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
@@ -0,0 +1,174 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::ast::MinifiedNode;
+use crate::merge::{merge_nodes, MergeStrategy};
+use crate::parse_minified::{parse_markdown_minified_with, MinifiedOptions};
+use crate::ConvertError;
+
+/// Which files [`parse_markdown_dir`] picks up
+#[derive(Debug, Clone, Default)]
+pub struct DirOptions {
+    /// Globs relative to the directory, e.g. `guides/**/*.md`.
+    /// When empty, every `.md` and `.markdown` file is included
+    pub include: Vec<String>,
+    /// Globs for files to leave out, e.g. `drafts/**`
+    pub exclude: Vec<String>,
+}
+
+/// Parses every matching file under `dir` into one map
+///
+/// Subdirectories become nested maps and files are keyed by their stem, so
+/// `guides/setup.md` ends up under `guides` → `setup`. Entries are sorted by
+/// key, and a file named like a sibling directory is merged into it.
+pub fn parse_markdown_dir(dir: &Path, dir_opts: &DirOptions, opts: &MinifiedOptions) -> Result<MinifiedNode, ConvertError> {
+    walk(dir, "", dir_opts, opts).map(|node| node.unwrap_or(MinifiedNode::Map(vec![])))
+}
+
+fn walk(dir: &Path, prefix: &str, dir_opts: &DirOptions, opts: &MinifiedOptions) -> Result<Option<MinifiedNode>, ConvertError> {
+    let fail = |path: &Path, e: &dyn std::fmt::Display| ConvertError::File {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    };
+
+    let entries = fs::read_dir(dir)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| fail(dir, &e))?;
+    // Symlinks are not followed, so a link to a parent can't make the walk loop
+    let mut entries = entries.into_iter()
+        .map(|entry| Ok((entry.file_type()?.is_dir(), entry)))
+        .collect::<io::Result<Vec<_>>>()
+        .map_err(|e| fail(dir, &e))?;
+    // By key, with `guides.md` ahead of `guides/`
+    entries.sort_by_cached_key(|(is_dir, entry)| (entry_key(&entry.path(), *is_dir), *is_dir));
+
+    let mut map: Vec<(String, MinifiedNode)> = vec![];
+    for (is_dir, entry) in entries {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let relative = format!("{prefix}{name}");
+
+        let (key, node) = if is_dir {
+            match walk(&path, &format!("{relative}/"), dir_opts, opts)? {
+                Some(node) => (entry_key(&path, true), node),
+                None => continue,
+            }
+        } else {
+            if !dir_opts.matches(&relative) {
+                continue;
+            }
+            let text = fs::read_to_string(&path).map_err(|e| fail(&path, &e))?;
+
+            let mut file_opts = opts.clone();
+            if let Some(includes) = &mut file_opts.includes {
+                includes.origin = Some(path.clone());
+            }
+            let node = parse_markdown_minified_with(&text, &file_opts).map_err(|e| fail(&path, &e))?;
+            (entry_key(&path, false), node)
+        };
+
+        match map.iter().position(|(k, _)| *k == key) {
+            Some(idx) => {
+                let existing = std::mem::replace(&mut map[idx].1, MinifiedNode::Null);
+                map[idx].1 = merge_nodes(existing, node, MergeStrategy::LaterWins)?;
+            }
+            None => map.push((key, node)),
+        }
+    }
+
+    Ok((!map.is_empty()).then_some(MinifiedNode::Map(map)))
+}
+
+/// Lists the files matching `pattern`, sorted
+///
+/// The segments before the first one with `*` or `?` name the directory to search.
+/// A pattern without wildcards is returned as is, whether or not the file exists.
+pub fn expand_glob(pattern: &str) -> io::Result<Vec<PathBuf>> {
+    let segments: Vec<&str> = pattern.split('/').collect();
+    let Some(split) = segments.iter().position(|s| s.contains(['*', '?'])) else {
+        return Ok(vec![PathBuf::from(pattern)]);
+    };
+
+    let base = match segments[..split].join("/") {
+        base if base.is_empty() && pattern.starts_with('/') => "/".to_string(),
+        base => base,
+    };
+    let rest = segments[split..].join("/");
+    // Without `**` there is no point looking deeper than the pattern goes
+    let max_depth = if rest.contains("**") { usize::MAX } else { segments.len() - split - 1 };
+
+    let mut found = vec![];
+    collect_matches(Path::new(&base), "", &rest, max_depth, &mut found)?;
+    found.sort();
+    Ok(found)
+}
+
+fn collect_matches(dir: &Path, prefix: &str, pattern: &str, depth: usize, found: &mut Vec<PathBuf>) -> io::Result<()> {
+    let search = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+
+    for entry in fs::read_dir(search)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let relative = format!("{prefix}{name}");
+        let path = dir.join(&name);
+
+        if entry.file_type()?.is_dir() {
+            if depth > 0 {
+                collect_matches(&path, &format!("{relative}/"), pattern, depth - 1, found)?;
+            }
+        } else if glob_match(pattern, &relative) {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Directories are keyed by name, files by stem
+fn entry_key(path: &Path, is_dir: bool) -> String {
+    let name = if is_dir { path.file_name() } else { path.file_stem() };
+    name.map(|s| s.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+impl DirOptions {
+    fn matches(&self, relative: &str) -> bool {
+        let included = if self.include.is_empty() {
+            relative.ends_with(".md") || relative.ends_with(".markdown")
+        } else {
+            self.include.iter().any(|p| glob_match(p, relative))
+        };
+        included && !self.exclude.iter().any(|p| glob_match(p, relative))
+    }
+}
+
+/// Matches a `/` separated path against a glob
+///
+/// `*` and `?` match within one path segment, `**` matches any number of segments.
+pub(crate) fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match_segments(&pattern, &path)
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| match_segments(rest, &path[skip..])),
+        Some((first, rest)) => match path.split_first() {
+            Some((segment, path_rest)) => {
+                let first: Vec<char> = first.chars().collect();
+                let segment: Vec<char> = segment.chars().collect();
+                match_segment(&first, &segment) && match_segments(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+fn match_segment(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| match_segment(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && match_segment(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && match_segment(rest, &name[1..]),
+    }
+}
@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use pretty_assertions::assert_eq;
+
+mod common;
+use common::fixture;
+
+fn md2data(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_md2data"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_cli_out_dir_names_outputs_by_stem() {
+    let dir = fixture("cli-batch", &[
+        ("docs/a.md", "# A\nx\n"),
+        ("docs/sub/b.markdown", "# B\ny\n"),
+    ]);
+
+    let out = md2data(&dir, &["docs/a.md", "docs/sub/b.markdown", "--out-dir", "build", "--compact"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(fs::read_to_string(dir.join("build/a.json")).unwrap(), r#"{"A":"x"}"#);
+    assert_eq!(fs::read_to_string(dir.join("build/b.json")).unwrap(), r#"{"B":"y"}"#);
+}
+
+#[test]
+fn test_cli_out_dir_reports_failed_files() {
+    let dir = fixture("cli-batch-errors", &[("a.md", "# A\nx\n")]);
+
+    let out = md2data(&dir, &["a.md", "missing.md", "--out-dir", "build"]);
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("missing.md: "), "{stderr}");
+    assert!(stderr.contains("1 of 2 files failed"), "{stderr}");
+
+    // The files that converted are still written
+    assert!(dir.join("build/a.json").exists());
+}
+
+#[test]
+fn test_cli_out_dir_refuses_clashing_names() {
+    let dir = fixture("cli-batch-clash", &[
+        ("a.md", "# A\nx\n"),
+        ("sub/a.md", "# A\ny\n"),
+    ]);
+
+    let out = md2data(&dir, &["a.md", "sub/a.md", "--out-dir", "build"]);
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("a.md and sub/a.md would both be written to build/a.json"), "{stderr}");
+    assert!(!dir.join("build").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_set_keeps_permissions_and_symlinks() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let dir = fixture("cli-set", &[("real.md", "# Port\n8080\n")]);
+    fs::set_permissions(dir.join("real.md"), fs::Permissions::from_mode(0o600)).unwrap();
+    symlink("real.md", dir.join("link.md")).unwrap();
+
+    let out = md2data(&dir, &["set", "link.md", "Port", "9090"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    assert!(fs::symlink_metadata(dir.join("link.md")).unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_to_string(dir.join("real.md")).unwrap(), "# Port\n9090\n");
+    assert_eq!(fs::metadata(dir.join("real.md")).unwrap().permissions().mode() & 0o777, 0o600);
+}
+
+// Polls until `path` holds `expected`, watch runs in the background
+fn wait_for(path: &Path, expected: &str) -> bool {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while std::time::Instant::now() < deadline {
+        if fs::read_to_string(path).is_ok_and(|text| text == expected) {
+            return true;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    false
+}
+
+#[test]
+fn test_cli_watch_reconverts_after_errors() {
+    let dir = fixture("cli-watch", &[("a.md", "# A\nx\n")]);
+    let mut watch = Command::new(env!("CARGO_BIN_EXE_md2data"))
+        .args(["watch", "a.md", "--out-dir", "build", "--debounce", "50", "--compact", "--embedded-data"])
+        .current_dir(&dir)
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    let out = dir.join("build/a.json");
+
+    let first = wait_for(&out, r#"{"A":"x"}"#);
+    // Invalid embedded JSON fails this round, the next edit is still picked up.
+    // Modification times can be coarse, so wait a little between writes
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    fs::write(dir.join("a.md"), "# A\n```json\n{oops\n```\n").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    fs::write(dir.join("a.md"), "# A\ny\n").unwrap();
+    let second = wait_for(&out, r#"{"A":"y"}"#);
+
+    watch.kill().unwrap();
+    watch.wait().unwrap();
+    assert!(first && second);
+}
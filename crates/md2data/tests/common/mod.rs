@@ -0,0 +1,30 @@
+// Helpers shared by the integration tests, not every test uses all of them
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+use md2data::MinifiedNode;
+
+// Writes `files` into a fresh directory under the system temp dir
+pub fn fixture(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("md2data-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for (path, text) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+    dir
+}
+
+// Collects the keys of a map for easier assertions
+pub fn keys(node: &MinifiedNode) -> Vec<&str> {
+    match node {
+        MinifiedNode::Map(map) => map.iter().map(|(k, _)| k.as_str()).collect(),
+        _ => panic!("Expected Map, got {:?}", node),
+    }
+}
+
+pub fn string(s: &str) -> MinifiedNode {
+    MinifiedNode::String(s.to_string())
+}
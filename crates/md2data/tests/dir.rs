@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+use md2data::{expand_glob, parse_markdown_dir, DirOptions, MinifiedNode, MinifiedOptions};
+use pretty_assertions::assert_eq;
+
+mod common;
+use common::{fixture, keys};
+
+fn get<'a>(node: &'a MinifiedNode, key: &str) -> &'a MinifiedNode {
+    match node {
+        MinifiedNode::Map(map) => &map.iter().find(|(k, _)| k == key).unwrap().1,
+        _ => panic!("Expected Map, got {:?}", node),
+    }
+}
+
+#[test]
+fn test_dir_nested_by_path() {
+    let dir = fixture("dir-nested", &[
+        ("zeta.md", "# Z\nlast\n"),
+        ("guides/setup.md", "# Steps\n- install\n"),
+        ("guides.md", "# Overview\nAll guides\n"),
+        ("alpha.markdown", "# A\nfirst\n"),
+        ("assets/logo.txt", "not markdown"),
+    ]);
+
+    let ast = parse_markdown_dir(&dir, &DirOptions::default(), &MinifiedOptions::default()).unwrap();
+
+    // Sorted by key, directories without Markdown are left out
+    assert_eq!(keys(&ast), vec!["alpha", "guides", "zeta"]);
+    // The file's own keys come before the directory's files
+    assert_eq!(keys(get(&ast, "guides")), vec!["Overview", "setup"]);
+    assert_eq!(
+        get(get(get(&ast, "guides"), "setup"), "Steps"),
+        &MinifiedNode::Array(vec![MinifiedNode::String("install".to_string())])
+    );
+}
+
+#[test]
+fn test_dir_include_exclude_globs() {
+    let dir = fixture("dir-globs", &[
+        ("index.md", "# Home\nx\n"),
+        ("guides/setup.md", "# Steps\nx\n"),
+        ("guides/drafts/wip.md", "# WIP\nx\n"),
+        ("notes/todo.txt", "# Todo\nx\n"),
+    ]);
+
+    let dir_opts = DirOptions {
+        include: vec!["guides/**/*.md".to_string(), "notes/*.txt".to_string()],
+        exclude: vec!["**/drafts/**".to_string()],
+    };
+    let ast = parse_markdown_dir(&dir, &dir_opts, &MinifiedOptions::default()).unwrap();
+
+    assert_eq!(keys(&ast), vec!["guides", "notes"]);
+    assert_eq!(keys(get(&ast, "guides")), vec!["setup"]);
+    assert_eq!(keys(get(&ast, "notes")), vec!["todo"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_dir_does_not_follow_symlinks() {
+    let dir = fixture("dir-symlink", &[("loop/a/page.md", "# Page\nx\n")]);
+    std::os::unix::fs::symlink("..", dir.join("loop/a/up")).unwrap();
+
+    let ast = parse_markdown_dir(&dir.join("loop"), &DirOptions::default(), &MinifiedOptions::default()).unwrap();
+    assert_eq!(keys(&ast), vec!["a"]);
+    assert_eq!(keys(get(&ast, "a")), vec!["page"]);
+}
+
+#[test]
+fn test_expand_glob() {
+    let dir = fixture("glob", &[
+        ("b.md", ""),
+        ("a.md", ""),
+        ("notes.txt", ""),
+        ("sub/c.md", ""),
+        ("sub/deeper/d.md", ""),
+    ]);
+    let base = dir.display().to_string();
+    let relative = |paths: Vec<PathBuf>| -> Vec<String> {
+        paths.iter().map(|p| p.strip_prefix(&dir).unwrap().display().to_string()).collect()
+    };
+
+    assert_eq!(relative(expand_glob(&format!("{base}/*.md")).unwrap()), vec!["a.md", "b.md"]);
+    assert_eq!(
+        relative(expand_glob(&format!("{base}/**/*.md")).unwrap()),
+        vec!["a.md", "b.md", "sub/c.md", "sub/deeper/d.md"]
+    );
+    assert_eq!(relative(expand_glob(&format!("{base}/*/?.md")).unwrap()), vec!["sub/c.md"]);
+
+    // Plain paths pass through untouched
+    assert_eq!(expand_glob("missing.md").unwrap(), vec![PathBuf::from("missing.md")]);
+}
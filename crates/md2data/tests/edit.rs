@@ -0,0 +1,72 @@
+use md2data::{parse_markdown_minified, set_section, MinifiedNode};
+use pretty_assertions::assert_eq;
+
+const CONFIG: &str = r#"# Server
+
+## Port
+<!-- type: int -->
+8080
+
+## Hosts
+* primary
+* backup
+
+## Empty
+
+# Notes
+Some *text*  here.
+"#;
+
+#[test]
+fn test_set_rewrites_only_the_section() {
+    let edited = set_section(CONFIG, "Server/Port", "9090").unwrap();
+    assert_eq!(edited, CONFIG.replace("8080", "9090"));
+
+    let edited = set_section(CONFIG, "/Server/Hosts", "- one\n- two\n").unwrap();
+    assert_eq!(edited, CONFIG.replace("* primary\n* backup", "- one\n- two"));
+
+    let ast = parse_markdown_minified(&edited);
+    assert_eq!(
+        ast.select("Server/Hosts").unwrap(),
+        &MinifiedNode::Array(vec![MinifiedNode::String("one".to_string()), MinifiedNode::String("two".to_string())])
+    );
+}
+
+#[test]
+fn test_set_empty_section() {
+    let edited = set_section(CONFIG, "Server/Empty", "yes").unwrap();
+    assert_eq!(edited, CONFIG.replace("## Empty\n", "## Empty\nyes\n"));
+
+    // The last section of the file, without a trailing newline
+    let edited = set_section("# A\nx\n# B", "B", "y").unwrap();
+    assert_eq!(edited, "# A\nx\n# B\ny\n");
+}
+
+#[test]
+fn test_set_keeps_comments_between_blocks() {
+    let edited = set_section("# A\n42\n\nmore\n<!-- c -->\n\ntail\n\n# B\nx\n", "A", "7").unwrap();
+    assert_eq!(edited, "# A\n7\n\n<!-- c -->\n\n# B\nx\n");
+}
+
+#[test]
+fn test_set_keeps_ignored_regions() {
+    let md = "# Server\n## Port\n8080\n\n<!-- md2data:ignore -->\nA note for readers.\n<!-- md2data:ignore-end -->\n\n8081\n\n# Other\nx\n";
+    let edited = set_section(md, "Server/Port", "9090").unwrap();
+    assert_eq!(
+        edited,
+        "# Server\n## Port\n9090\n\n<!-- md2data:ignore -->\nA note for readers.\n<!-- md2data:ignore-end -->\n\n# Other\nx\n"
+    );
+
+    // Key directives rename the heading they precede
+    let md = "<!-- md2data:key port -->\n# Port number\n8080\n";
+    assert_eq!(set_section(md, "port", "1").unwrap(), md.replace("8080", "1"));
+}
+
+#[test]
+fn test_set_errors() {
+    let err = set_section(CONFIG, "Server", "x").unwrap_err();
+    assert_eq!(err.to_string(), r#"cannot set "Server": it has subsections, only sections without subheadings can be set"#);
+
+    let err = set_section(CONFIG, "Server/Timeout", "x").unwrap_err();
+    assert_eq!(err.to_string(), r#"path "Server/Timeout" not found: "Server" has no "Timeout""#);
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::path::Path;
+use md2data::{parse_markdown_minified_with, IncludeOptions, MinifiedNode, MinifiedOptions};
+use pretty_assertions::assert_eq;
+
+mod common;
+use common::{fixture, string};
+
+fn parse(dir: &Path, file: &str) -> Result<MinifiedNode, md2data::ConvertError> {
+    let origin = dir.join(file);
+    let opts = MinifiedOptions {
+        includes: Some(IncludeOptions { root: dir.to_path_buf(), origin: Some(origin.clone()) }),
+        ..Default::default()
+    };
+    parse_markdown_minified_with(&fs::read_to_string(origin).unwrap(), &opts)
+}
+
+#[test]
+fn test_include_splices_under_heading() {
+    let dir = fixture("splice", &[
+        ("main.md", "<!-- md2data:include parts/meta.md -->\n\n# Database\n<!-- md2data:include parts/db.md -->\n\n# Server\n{{#include parts/server.md}}\n"),
+        ("parts/meta.md", "# Name\ndemo\n"),
+        // A directive can span several lines
+        ("parts/db.md", "# Host\nlocalhost\n\n# Replica\n<!--\nmd2data:include replica.md\n-->\n"),
+        ("parts/replica.md", "# Host\nbackup\n"),
+        ("parts/server.md", "# Port\n80\n"),
+    ]);
+
+    let ast = parse(&dir, "main.md").unwrap();
+    assert_eq!(ast, MinifiedNode::Map(vec![
+        ("Name".to_string(), string("demo")),
+        ("Database".to_string(), MinifiedNode::Map(vec![
+            ("Host".to_string(), string("localhost")),
+            // Nested includes resolve relative to the including file
+            ("Replica".to_string(), MinifiedNode::Map(vec![("Host".to_string(), string("backup"))])),
+        ])),
+        ("Server".to_string(), MinifiedNode::Map(vec![("Port".to_string(), string("80"))])),
+    ]));
+}
+
+#[test]
+fn test_include_cycle_and_sandbox() {
+    // The include root is a subdirectory, so the file outside it stays inside the fixture
+    let fixture = fixture("cycle", &[
+        ("root/a.md", "# A\n<!-- md2data:include b.md -->\n"),
+        ("root/b.md", "# B\n<!-- md2data:include a.md -->\n"),
+        ("root/escape.md", "# Outside\n<!-- md2data:include ../escape.md -->\n"),
+        ("escape.md", "# Secret\nx\n"),
+    ]);
+    let dir = fixture.join("root");
+
+    let err = parse(&dir, "a.md").unwrap_err();
+    assert_eq!(err.to_string(), r#"cannot include "a.md": include cycle a.md -> b.md -> a.md"#);
+
+    let err = parse(&dir, "escape.md").unwrap_err();
+    assert!(err.to_string().contains("outside of the include root"), "{err}");
+}
+
+#[test]
+fn test_include_rejects_section_with_text() {
+    let dir = fixture("mixed", &[
+        ("before.md", "## A\nfrom a\n<!-- md2data:include b.md -->\n"),
+        ("after.md", "## A\n{{#include b.md}}\n\nmore\n\n## C\nx\n"),
+        ("b.md", "# B\nb\n"),
+    ]);
+
+    for file in ["before.md", "after.md"] {
+        let err = parse(&dir, file).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"cannot include "b.md": "A" also has text, move it to a subsection or into the included file"#
+        );
+    }
+}
+
+#[test]
+fn test_include_ignored_without_options() {
+    let md = "# A\n<!-- md2data:include missing.md -->\n";
+    let ast = parse_markdown_minified_with(md, &MinifiedOptions::default()).unwrap();
+    assert_eq!(ast, MinifiedNode::Map(vec![("A".to_string(), string(""))]));
+}
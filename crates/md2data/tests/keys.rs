@@ -0,0 +1,181 @@
+use md2data::{
+    is_toml_bare_key, parse_markdown_minified, parse_markdown_minified_with, sanitize_key, sanitize_keys,
+    sanitize_keys_for, transform_key, transform_keys, CollisionPolicy, ConvertError, KeyCase, KeyProfile,
+    MinifiedNode, MinifiedOptions, OrderedListFormat, TitleMode,
+};
+use pretty_assertions::assert_eq;
+
+mod common;
+use common::keys;
+
+#[test]
+fn test_key_case_styles() {
+    let key = "Build Settings";
+    assert_eq!(transform_key(key, KeyCase::Snake), "build_settings");
+    assert_eq!(transform_key(key, KeyCase::Camel), "buildSettings");
+    assert_eq!(transform_key(key, KeyCase::Kebab), "build-settings");
+    assert_eq!(transform_key(key, KeyCase::ScreamingSnake), "BUILD_SETTINGS");
+    assert_eq!(transform_key(key, KeyCase::Slug), "build-settings");
+
+    // camelCase humps and non-ASCII characters
+    assert_eq!(transform_key("maxRetryCount", KeyCase::Snake), "max_retry_count");
+    assert_eq!(transform_key("Café Menu", KeyCase::Slug), "caf-menu");
+}
+
+#[test]
+fn test_key_case_strips_colons_and_numbering() {
+    assert_eq!(transform_key("Settings:", KeyCase::Snake), "settings");
+    assert_eq!(transform_key("1.2 Intro", KeyCase::Snake), "intro");
+    assert_eq!(transform_key("3) Next Steps", KeyCase::Camel), "nextSteps");
+
+    // A bare number is part of the title, not numbering
+    assert_eq!(transform_key("2024 Roadmap", KeyCase::Snake), "2024_roadmap");
+}
+
+#[test]
+fn test_transform_keys_nested() {
+    let md = r#"
+# Build Settings
+## Target Dir
+build/
+"#;
+    let ast = transform_keys(parse_markdown_minified(md), KeyCase::Snake).unwrap();
+    assert_eq!(keys(&ast), vec!["build_settings"]);
+
+    if let MinifiedNode::Map(map) = &ast {
+        assert_eq!(keys(&map[0].1), vec!["target_dir"]);
+    }
+}
+
+#[test]
+fn test_transform_keys_collision() {
+    let md = r#"
+# Build Settings
+a
+
+# build_settings
+b
+"#;
+    let err = transform_keys(parse_markdown_minified(md), KeyCase::Snake).unwrap_err();
+    match err {
+        ConvertError::KeyCollision { key, first, second } => {
+            assert_eq!(key, "build_settings");
+            assert_eq!(first, "Build Settings");
+            assert_eq!(second, "build_settings");
+        }
+        _ => panic!("Expected KeyCollision, got {:?}", err),
+    }
+}
+
+#[test]
+fn test_sanitize_keys_disambiguates() {
+    let md = r#"
+# My Key
+a
+
+# My_Key
+b
+
+# My_Key_2
+c
+"#;
+    let ast = sanitize_keys(parse_markdown_minified(md));
+    assert_eq!(keys(&ast), vec!["My_Key", "My_Key_2", "My_Key_2_2"]);
+}
+
+#[test]
+fn test_sanitize_keys_for_reports_headings() {
+    let md = r#"
+# 1st
+a
+
+# _1st
+b
+"#;
+    let err = sanitize_keys_for(parse_markdown_minified(md), KeyProfile::Xml, CollisionPolicy::Error).unwrap_err();
+    assert_eq!(err.to_string(), r#"key collision: "1st" and "_1st" both become "_1st""#);
+}
+
+#[test]
+fn test_sanitize_key_profiles() {
+    assert_eq!(sanitize_key("1st Item", KeyProfile::Xml), "_1st_Item");
+
+    assert_eq!(sanitize_key("Build Settings", KeyProfile::Toml), r#""Build Settings""#);
+    assert_eq!(sanitize_key(r#"a"b"#, KeyProfile::Toml), r#""a\"b""#);
+    assert_eq!(sanitize_key("max-retries", KeyProfile::Toml), "max-retries");
+    assert!(is_toml_bare_key("max-retries"));
+    assert!(!is_toml_bare_key("max retries"));
+
+    assert_eq!(sanitize_key("api-key", KeyProfile::Env), "API_KEY");
+    assert_eq!(sanitize_key("2fa secret", KeyProfile::Env), "_2FA_SECRET");
+
+    assert_eq!(sanitize_key(" [core] = x ", KeyProfile::Ini), "_core_ _ x");
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_toml_quotes_non_bare_keys() {
+    let md = "# Build Settings\na\n\n# Build_Settings\nb\n\n# über\nc\n";
+    let out = md2data::convert_str(md, md2data::OutputFormat::Toml, md2data::ParsingMode::Minified).unwrap();
+    assert_eq!(out, "\"Build Settings\" = \"a\"\nBuild_Settings = \"b\"\n\"über\" = \"c\"\n");
+}
+
+#[test]
+fn test_repeated_headings_are_not_collisions() {
+    let md = "# Notes\na\n\n# Notes\nb\n";
+    let ast = transform_keys(parse_markdown_minified(md), KeyCase::Snake).unwrap();
+    assert_eq!(keys(&ast), vec!["notes", "notes"]);
+}
+
+#[cfg(feature = "xml")]
+#[test]
+fn test_xml_repeated_headings() {
+    let md = "# Notes\na\n\n# Notes\n";
+    let out = md2data::convert_str(md, md2data::OutputFormat::Xml, md2data::ParsingMode::Minified).unwrap();
+    assert_eq!(out, "<Document><Notes>a</Notes><Notes/></Document>");
+}
+
+#[test]
+fn test_key_case_only_rewrites_headings() {
+    let md = r#"
+# Build Settings
+```json
+{"aB": 1, "a_b": 2}
+```
+
+# Release Steps
+1. tag
+"#;
+    let opts = MinifiedOptions {
+        key_case: Some(KeyCase::ScreamingSnake),
+        embedded_data: true,
+        ordered_lists: OrderedListFormat::Object,
+        ..Default::default()
+    };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    assert_eq!(keys(&ast), vec!["BUILD_SETTINGS", "RELEASE_STEPS"]);
+
+    let MinifiedNode::Map(map) = &ast else { panic!("Expected Map") };
+    assert_eq!(keys(&map[0].1), vec!["aB", "a_b"]);
+    assert_eq!(keys(&map[1].1), vec!["ordered", "start", "items"]);
+}
+
+#[test]
+fn test_key_case_heading_collisions() {
+    let md = "# Build Settings\na\n\n# build_settings\nb\n\n# Build Settings\nc\n";
+    let opts = MinifiedOptions { key_case: Some(KeyCase::Snake), ..Default::default() };
+    let err = parse_markdown_minified_with(md, &opts).unwrap_err();
+    assert_eq!(err.to_string(), r#"key collision: "Build Settings" and "build_settings" both become "build_settings""#);
+
+    let opts = MinifiedOptions { key_collisions: CollisionPolicy::Disambiguate, ..opts };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    assert_eq!(keys(&ast), vec!["build_settings", "build_settings_2", "build_settings"]);
+
+    // A hoisted title keeps the heading as written
+    let opts = MinifiedOptions { title_mode: TitleMode::Hoist, ..opts };
+    let ast = parse_markdown_minified_with("# My Doc\n## Intro\nhi\n", &opts).unwrap();
+    assert_eq!(ast, MinifiedNode::Map(vec![
+        ("title".to_string(), MinifiedNode::String("My Doc".to_string())),
+        ("intro".to_string(), MinifiedNode::String("hi".to_string())),
+    ]));
+}
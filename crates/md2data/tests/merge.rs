@@ -0,0 +1,75 @@
+use md2data::{merge_markdown, merge_nodes, MergeStrategy, MinifiedNode, MinifiedOptions};
+use pretty_assertions::assert_eq;
+
+mod common;
+use common::string;
+
+const BASE: &str = r#"
+# Server
+## Host
+localhost
+
+## Port
+80
+
+# Tags
+- base
+"#;
+
+const OVERRIDE: &str = r#"
+# Server
+## Port
+8080
+
+# Tags
+- local
+
+# Debug
+true
+"#;
+
+#[test]
+fn test_merge_later_wins() {
+    let ast = merge_markdown(&[BASE, OVERRIDE], &MinifiedOptions::default(), MergeStrategy::LaterWins).unwrap();
+    assert_eq!(ast, MinifiedNode::Map(vec![
+        ("Server".to_string(), MinifiedNode::Map(vec![
+            ("Host".to_string(), string("localhost")),
+            ("Port".to_string(), string("8080")),
+        ])),
+        ("Tags".to_string(), MinifiedNode::Array(vec![string("local")])),
+        ("Debug".to_string(), string("true")),
+    ]));
+}
+
+#[test]
+fn test_merge_concat_arrays() {
+    let ast = merge_markdown(&[BASE, OVERRIDE], &MinifiedOptions::default(), MergeStrategy::ConcatArrays).unwrap();
+    let MinifiedNode::Map(map) = ast else { panic!("Expected Map") };
+    assert_eq!(map[1].1, MinifiedNode::Array(vec![string("base"), string("local")]));
+}
+
+#[test]
+fn test_merge_error_names_key() {
+    let err = merge_markdown(&[BASE, OVERRIDE], &MinifiedOptions::default(), MergeStrategy::Error).unwrap_err();
+    assert_eq!(err.to_string(), r#"merge conflict at "Server/Port""#);
+
+    // Identical values are not a conflict
+    let a = MinifiedNode::Map(vec![("Name".to_string(), string("x"))]);
+    assert_eq!(merge_nodes(a.clone(), a.clone(), MergeStrategy::Error).unwrap(), a);
+}
+
+#[test]
+fn test_merge_split_records() {
+    let opts = MinifiedOptions { split_on_break: true, ..Default::default() };
+    let a = "# A\n1\n\n***\n\n# B\n2\n";
+    let b = "# C\n3\n";
+
+    // The first input is not merged against an empty map, so it can't conflict
+    let ast = merge_markdown(&[a], &opts, MergeStrategy::Error).unwrap();
+    let MinifiedNode::Array(records) = ast else { panic!("Expected Array") };
+    assert_eq!(records.len(), 2);
+
+    let ast = merge_markdown(&[a, b], &opts, MergeStrategy::ConcatArrays).unwrap();
+    let MinifiedNode::Array(records) = ast else { panic!("Expected Array") };
+    assert_eq!(records[2], MinifiedNode::Map(vec![("C".to_string(), string("3"))]));
+}
@@ -0,0 +1,65 @@
+use md2data::{convert_str, convert_str_with, ConvertOptions, OutputFormat, OutputStyle, ParsingMode, TrailingNewline};
+use pretty_assertions::assert_eq;
+
+const INPUT: &str = r#"
+# Zeta
+## Beta
+1
+
+## Alpha
+- x
+- y
+
+# Alpha
+first
+"#;
+
+fn convert(fmt: OutputFormat, style: OutputStyle) -> String {
+    let opts = ConvertOptions { style, ..Default::default() };
+    convert_str_with(INPUT, fmt, ParsingMode::Minified, &opts).unwrap()
+}
+
+#[test]
+fn test_output_default_style_unchanged() {
+    let default = convert_str(INPUT, OutputFormat::Json, ParsingMode::Minified).unwrap();
+    assert_eq!(convert(OutputFormat::Json, OutputStyle::default()), default);
+    assert!(default.starts_with("{\n  \"Zeta\": {"));
+    assert!(!default.ends_with('\n'));
+}
+
+#[test]
+fn test_output_compact_sorted() {
+    let style = OutputStyle { compact: true, sort_keys: true, ..Default::default() };
+    assert_eq!(
+        convert(OutputFormat::Json, style),
+        r#"{"Alpha":"first","Zeta":{"Alpha":["x","y"],"Beta":"1"}}"#
+    );
+}
+
+#[test]
+fn test_output_indent_and_trailing_newline() {
+    let style = OutputStyle { indent: Some(4), trailing_newline: TrailingNewline::Always, ..Default::default() };
+    let out = convert(OutputFormat::Json, style);
+    assert!(out.starts_with("{\n    \"Zeta\": {\n        \"Beta\": \"1\""));
+    assert!(out.ends_with("}\n"));
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_output_trailing_newline_never() {
+    let style = OutputStyle { trailing_newline: TrailingNewline::Never, ..Default::default() };
+    assert!(convert(OutputFormat::Yaml, OutputStyle::default()).ends_with('\n'));
+    assert!(!convert(OutputFormat::Yaml, style).ends_with('\n'));
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_output_toml_drops_nulls() {
+    let md = "# Data\n```json\n{\"a\": null, \"b\": [1, null, 2], \"c\": true}\n```\n";
+    let opts = ConvertOptions {
+        minified: md2data::MinifiedOptions { embedded_data: true, ..Default::default() },
+        ..Default::default()
+    };
+    let out = convert_str_with(md, OutputFormat::Toml, ParsingMode::Minified, &opts).unwrap();
+    assert_eq!(out, "[Data]\nb = [\n    1,\n    2,\n]\nc = true\n");
+}
@@ -90,3 +90,34 @@ fn test_simple_heading1_2() {
         panic!("Not a Document");
     }
 }
+
+/// Ignore directives drop the region between them
+///
+/// INPUT
+/// # Kept
+/// <!-- md2data:ignore -->
+/// # Dropped
+/// <!-- md2data:ignore-end -->
+///
+/// OUTPUT
+/// Document:
+///     Heading:
+///         level: 1
+///         children:
+///             "Kept"
+#[test]
+fn test_ignore_directive() {
+    let md = "# Kept\n\n<!-- md2data:ignore -->\n# Dropped\n\ntext\n<!-- md2data:ignore-end -->\n";
+    let ast = parse_markdown(md);
+
+    if let Node::Document { children } = ast {
+        assert_eq!(children.len(), 1);
+        if let Node::Heading { children, .. } = unwrap_node(&children[0]) {
+            assert_eq!(unwrap_text(&children[0]), "Kept");
+        } else {
+            panic!("Not a Heading");
+        }
+    } else {
+        panic!("Not a Document");
+    }
+}
@@ -1,4 +1,9 @@
-use md2data::{parse_markdown_minified, MinifiedNode};
+use std::num::NonZeroUsize;
+use md2data::{
+    parse_markdown_minified, parse_markdown_minified_with, CodeBlockFormat, ConvertError, CsvOptions,
+    EmptySection, LeafFormat, ListItemFormat, MinifiedNode, MinifiedOptions, OrderedListFormat,
+    SectionFormat, TitleMode,
+};
 use pretty_assertions::assert_eq;
 
 // Helper to extract string value for easier assertions
@@ -191,4 +196,554 @@ fn test_minified_nested_list_items() {
     // matrix[3] = Array with Col 3
     let row2_nested = unwrap_array(&matrix[3]);
     assert_eq!(unwrap_string(&row2_nested[0]), "Col 3");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_minified_leaf_formats() {
+    let md = r#"
+# Description
+See the **full** [docs](https://x) for `md2data`.
+
+# Links
+* [Home](https://home)
+* Plain item
+"#;
+    // Plain (default) drops inline markup
+    let ast = parse_markdown_minified(md);
+    let map = unwrap_map(&ast);
+    assert_eq!(unwrap_string(&map[0].1), "See the full docs for md2data.");
+
+    // Markdown keeps the source slice
+    let opts = MinifiedOptions { leaf_format: LeafFormat::Markdown, ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let map = unwrap_map(&ast);
+    assert_eq!(unwrap_string(&map[0].1), "See the **full** [docs](https://x) for `md2data`.");
+    let links = unwrap_array(&map[1].1);
+    assert_eq!(unwrap_string(&links[0]), "[Home](https://home)");
+    assert_eq!(unwrap_string(&links[1]), "Plain item");
+
+    // HTML renders the inline markup
+    let opts = MinifiedOptions { leaf_format: LeafFormat::Html, ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let map = unwrap_map(&ast);
+    assert_eq!(
+        unwrap_string(&map[0].1),
+        r#"See the <strong>full</strong> <a href="https://x">docs</a> for <code>md2data</code>."#
+    );
+}
+
+#[test]
+fn test_minified_section_blocks() {
+    let md = r#"
+# Steps
+Before you start:
+
+1. Install
+2. Configure
+
+```sh
+make
+```
+
+# Single
+Only text
+"#;
+    // Joined (default) flattens everything into one string
+    let ast = parse_markdown_minified(md);
+    let map = unwrap_map(&ast);
+    assert!(unwrap_string(&map[0].1).starts_with("Before you start:\n\n1. Install"));
+
+    // Blocks keeps each block as its own value
+    let opts = MinifiedOptions { section_format: SectionFormat::Blocks, ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let map = unwrap_map(&ast);
+
+    let steps = unwrap_array(&map[0].1);
+    assert_eq!(steps.len(), 3);
+    assert_eq!(unwrap_string(&steps[0]), "Before you start:");
+    let list = unwrap_array(&steps[1]);
+    assert_eq!(unwrap_string(&list[0]), "Install");
+    assert_eq!(unwrap_string(&steps[2]), "```sh\nmake\n```\n");
+
+    // A single block keeps its own value
+    assert_eq!(unwrap_string(&map[1].1), "Only text");
+}
+
+#[test]
+fn test_minified_code_formats() {
+    let md = r#"
+# Build
+```sh
+make
+```
+
+```sh
+make install
+```
+
+# Example
+```rust
+fn main() {}
+```
+"#;
+    // Objects: several blocks under one key become an array
+    let opts = MinifiedOptions { code_format: CodeBlockFormat::Object, ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let map = unwrap_map(&ast);
+
+    let build = unwrap_array(&map[0].1);
+    assert_eq!(build.len(), 2);
+    let first = unwrap_map(&build[0]);
+    assert_eq!(first[0].0, "lang");
+    assert_eq!(unwrap_string(&first[0].1), "sh");
+    assert_eq!(first[1].0, "code");
+    assert_eq!(unwrap_string(&first[1].1), "make\n");
+
+    let example = unwrap_map(&map[1].1);
+    assert_eq!(unwrap_string(&example[1].1), "fn main() {}\n");
+
+    // Raw: just the body
+    let opts = MinifiedOptions { code_format: CodeBlockFormat::Raw, ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let map = unwrap_map(&ast);
+    assert_eq!(unwrap_string(&map[1].1), "fn main() {}\n");
+}
+
+#[test]
+fn test_minified_embedded_data() {
+    let md = r#"
+# Server
+```json
+{ "port": 8080, "debug": true, "hosts": ["a", "b"], "proxy": null }
+```
+
+# Script
+```sh
+echo hi
+```
+"#;
+    let opts = MinifiedOptions { embedded_data: true, ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let map = unwrap_map(&ast);
+
+    // Data keeps its types and key order
+    let server = unwrap_map(&map[0].1);
+    assert_eq!(server[0], ("port".to_string(), MinifiedNode::Integer(8080)));
+    assert_eq!(server[1], ("debug".to_string(), MinifiedNode::Bool(true)));
+    assert_eq!(unwrap_array(&server[2].1).len(), 2);
+    assert_eq!(server[3], ("proxy".to_string(), MinifiedNode::Null));
+
+    // Other languages stay code blocks
+    assert_eq!(unwrap_string(&map[1].1), "```sh\necho hi\n```");
+
+    // Disabled by default
+    let ast = parse_markdown_minified(md);
+    assert!(unwrap_string(&unwrap_map(&ast)[0].1).starts_with("```json"));
+}
+
+#[test]
+fn test_minified_embedded_data_invalid() {
+    let md = r#"
+# Server
+```json
+{ "port": 8080,
+```
+"#;
+    let opts = MinifiedOptions { embedded_data: true, ..Default::default() };
+    let err = parse_markdown_minified_with(md, &opts).unwrap_err();
+    match err {
+        ConvertError::EmbeddedData { key, lang, .. } => {
+            assert_eq!(key, "Server");
+            assert_eq!(lang, "json");
+        }
+        _ => panic!("Expected EmbeddedData, got {:?}", err),
+    }
+}
+
+#[test]
+fn test_minified_csv_records() {
+    let md = r#"
+# Hosts
+```csv
+name,port,mode,note
+web,8080,0755,"says ""hi"", twice"
+db,5432,,
+```
+
+# Tabs
+```tsv
+a	b
+1	x
+```
+"#;
+    // Strings only by default
+    let csv = CsvOptions::default();
+    let opts = MinifiedOptions { csv: Some(csv), ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let map = unwrap_map(&ast);
+
+    let hosts = unwrap_array(&map[0].1);
+    assert_eq!(hosts.len(), 2);
+    let web = unwrap_map(&hosts[0]);
+    assert_eq!(web[0].0, "name");
+    assert_eq!(unwrap_string(&web[1].1), "8080");
+    assert_eq!(unwrap_string(&web[3].1), r#"says "hi", twice"#);
+
+    let tabs = unwrap_array(&map[1].1);
+    assert_eq!(unwrap_string(&unwrap_map(&tabs[0])[1].1), "x");
+
+    // Type inference keeps leading zeros as strings
+    let csv = CsvOptions { infer_types: true, ..Default::default() };
+    let opts = MinifiedOptions { csv: Some(csv), ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let hosts = unwrap_array(&unwrap_map(&ast)[0].1);
+    let web = unwrap_map(&hosts[0]);
+    assert_eq!(web[1].1, MinifiedNode::Integer(8080));
+    assert_eq!(unwrap_string(&web[2].1), "0755");
+    assert_eq!(unwrap_map(&hosts[1])[2].1, MinifiedNode::Null);
+}
+
+#[test]
+fn test_minified_csv_custom_delimiter_and_errors() {
+    let md = "# Rows\n```csv\na;b\n1;2\n```\n";
+    let csv = CsvOptions { delimiter: Some(';'), ..Default::default() };
+    let opts = MinifiedOptions { csv: Some(csv), ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let rows = unwrap_array(&unwrap_map(&ast)[0].1);
+    assert_eq!(unwrap_map(&rows[0])[1].0, "b");
+
+    let md = "# Rows\n```csv\na,b\n1\n```\n";
+    let opts = MinifiedOptions { csv: Some(CsvOptions::default()), ..Default::default() };
+    let err = parse_markdown_minified_with(md, &opts).unwrap_err();
+    assert_eq!(err.to_string(), r#"invalid embedded csv under "Rows": row 2 has 1 fields, expected 2"#);
+}
+
+#[test]
+fn test_minified_list_item_objects() {
+    let md = r#"
+# Authors
+* Jenul
+* Foo
+    * Bar1
+    * Bar2
+"#;
+    // Keyed: the item text becomes the key
+    let opts = MinifiedOptions { list_items: ListItemFormat::Keyed, ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let authors = unwrap_array(&unwrap_map(&ast)[0].1);
+    assert_eq!(authors.len(), 2);
+    assert_eq!(unwrap_string(&authors[0]), "Jenul");
+    let foo = unwrap_map(&authors[1]);
+    assert_eq!(foo[0].0, "Foo");
+    assert_eq!(unwrap_string(&unwrap_array(&foo[0].1)[1]), "Bar2");
+
+    // TextChildren: explicit fields
+    let opts = MinifiedOptions { list_items: ListItemFormat::TextChildren, ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let authors = unwrap_array(&unwrap_map(&ast)[0].1);
+    let foo = unwrap_map(&authors[1]);
+    assert_eq!(foo[0].0, "text");
+    assert_eq!(unwrap_string(&foo[0].1), "Foo");
+    assert_eq!(foo[1].0, "children");
+    assert_eq!(unwrap_array(&foo[1].1).len(), 2);
+}
+
+#[test]
+fn test_minified_ordered_lists() {
+    let md = r#"
+# Steps
+Do this:
+
+3. Install
+4. Configure
+   * carefully
+"#;
+    // Stringified ordered lists keep their numbering
+    let ast = parse_markdown_minified(md);
+    assert_eq!(
+        unwrap_string(&unwrap_map(&ast)[0].1),
+        "Do this:\n\n3. Install\n4. Configure\n   * carefully"
+    );
+
+    // Object marks the list as ordered
+    let md = "# Steps\n1. Install\n2. Configure\n";
+    let opts = MinifiedOptions { ordered_lists: OrderedListFormat::Object, ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let steps = unwrap_map(&unwrap_map(&ast)[0].1);
+    assert_eq!(steps[0], ("ordered".to_string(), MinifiedNode::Bool(true)));
+    assert_eq!(steps[1], ("start".to_string(), MinifiedNode::Integer(1)));
+    assert_eq!(unwrap_array(&steps[2].1).len(), 2);
+
+    // Bullet lists are unaffected
+    let ast = parse_markdown_minified_with("# Tags\n* a\n* b\n", &opts).unwrap();
+    assert_eq!(unwrap_array(&unwrap_map(&ast)[0].1).len(), 2);
+}
+
+#[test]
+fn test_minified_empty_sections() {
+    let md = r#"
+# Empty
+# Full
+x
+# Parent
+## Child
+"#;
+    // EmptyString (default)
+    let ast = parse_markdown_minified(md);
+    assert_eq!(unwrap_string(&unwrap_map(&ast)[0].1), "");
+
+    let parse = |policy| {
+        let opts = MinifiedOptions { empty_sections: policy, ..Default::default() };
+        parse_markdown_minified_with(md, &opts).unwrap()
+    };
+
+    let ast = parse(EmptySection::Null);
+    let map = unwrap_map(&ast);
+    assert_eq!(map[0].1, MinifiedNode::Null);
+    assert_eq!(unwrap_string(&map[1].1), "x");
+    assert_eq!(unwrap_map(&map[2].1)[0].1, MinifiedNode::Null);
+
+    let ast = parse(EmptySection::EmptyMap);
+    assert_eq!(unwrap_map(&ast)[0].1, MinifiedNode::Map(vec![]));
+
+    // Omit drops the key, leaving its parent empty
+    let ast = parse(EmptySection::Omit);
+    let map = unwrap_map(&ast);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map[0].0, "Full");
+    assert_eq!(map[1].1, MinifiedNode::Map(vec![]));
+}
+
+#[test]
+fn test_minified_max_depth() {
+    let md = r#"
+# Guide
+Intro
+
+## Setup
+Install it.
+
+### Linux
+Use apt.
+
+# Other
+## Child
+x
+"#;
+    let opts = MinifiedOptions { max_depth: NonZeroUsize::new(1), ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let map = unwrap_map(&ast);
+
+    assert_eq!(map.len(), 2);
+    assert_eq!(
+        unwrap_string(&map[0].1),
+        "Intro\n\n## Setup\n\nInstall it.\n\n### Linux\n\nUse apt."
+    );
+    assert_eq!(unwrap_string(&map[1].1), "## Child\n\nx");
+
+    // Depth 2 keeps the second level as keys
+    let opts = MinifiedOptions { max_depth: NonZeroUsize::new(2), ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let guide = unwrap_map(&unwrap_map(&ast)[0].1);
+    assert_eq!(guide[0].0, "Setup");
+    assert_eq!(unwrap_string(&guide[0].1), "Install it.\n\n### Linux\n\nUse apt.");
+
+    // Folded headings keep their inline markup like the text around them
+    let opts = MinifiedOptions { max_depth: NonZeroUsize::new(1), leaf_format: LeafFormat::Markdown, ..Default::default() };
+    let ast = parse_markdown_minified_with("# A\n*x*\n## *B* `c`\ny\n", &opts).unwrap();
+    assert_eq!(unwrap_string(&unwrap_map(&ast)[0].1), "*x*\n\n## *B* `c`\n\ny");
+
+    // A key override replaces the folded heading's text
+    let ast = parse_markdown_minified_with("# A\nx\n<!-- md2data:key Name -->\n## Real *Name*\ny\n", &opts).unwrap();
+    assert_eq!(unwrap_string(&unwrap_map(&ast)[0].1), "x\n\n## Name\n\ny");
+}
+
+#[test]
+fn test_minified_title_mode() {
+    let md = r#"
+# My Doc
+## Intro
+Hello
+
+## Usage
+Run it
+"#;
+    let parse = |mode| {
+        let opts = MinifiedOptions { title_mode: mode, ..Default::default() };
+        parse_markdown_minified_with(md, &opts).unwrap()
+    };
+
+    let ast = parse(TitleMode::Hoist);
+    let map = unwrap_map(&ast);
+    assert_eq!(map.len(), 3);
+    assert_eq!(map[0].0, "title");
+    assert_eq!(unwrap_string(&map[0].1), "My Doc");
+    assert_eq!(map[1].0, "Intro");
+
+    let ast = parse(TitleMode::Promote);
+    let map = unwrap_map(&ast);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map[0].0, "Intro");
+    assert_eq!(unwrap_string(&map[1].1), "Run it");
+
+    // Several top-level headings are left alone
+    let md = "# A\nx\n# B\ny\n";
+    let opts = MinifiedOptions { title_mode: TitleMode::Promote, ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    assert_eq!(unwrap_map(&ast)[0].0, "A");
+}
+
+#[test]
+fn test_minified_directives() {
+    let md = r#"
+# Name
+md2data
+
+<!-- md2data:ignore -->
+# Notes
+Prose that should not leak into the data.
+<!-- md2data:ignore-end -->
+
+<!-- md2data:key port -->
+# Port Number
+8080
+
+<!-- a regular comment -->
+# Host
+localhost
+"#;
+    let ast = parse_markdown_minified(md);
+    let map = unwrap_map(&ast);
+
+    assert_eq!(map.len(), 3);
+    assert_eq!(map[0].0, "Name");
+    assert_eq!(map[1].0, "port");
+    assert_eq!(unwrap_string(&map[1].1), "8080");
+    assert_eq!(map[2].0, "Host");
+}
+
+#[test]
+fn test_minified_type_hints() {
+    let md = r#"
+# Port
+<!-- type: int -->
+8080
+
+# Mode
+<!-- type: string -->
+0755
+
+# Ratio
+<!-- type: float -->
+0.5
+
+# Enabled
+<!-- type: bool -->
+true
+
+# Hosts
+<!-- type: list<string> -->
+localhost
+
+# Ports
+<!-- type: list<int> -->
+* 80
+* 443
+
+# Count
+<!--
+type: int
+-->
+3
+"#;
+    let ast = parse_markdown_minified(md);
+    let map = unwrap_map(&ast);
+
+    assert_eq!(map[0].1, MinifiedNode::Integer(8080));
+    assert_eq!(unwrap_string(&map[1].1), "0755");
+    assert_eq!(map[2].1, MinifiedNode::Float(0.5));
+    assert_eq!(map[3].1, MinifiedNode::Bool(true));
+    assert_eq!(map[4].1, MinifiedNode::Array(vec![MinifiedNode::String("localhost".to_string())]));
+    assert_eq!(map[5].1, MinifiedNode::Array(vec![MinifiedNode::Integer(80), MinifiedNode::Integer(443)]));
+    // Hints can span several lines
+    assert_eq!(map[6].1, MinifiedNode::Integer(3));
+}
+
+#[test]
+fn test_minified_type_hint_diagnostic() {
+    let md = "# Server\n## Port\n<!-- type: int -->\neighty\n";
+    let err = parse_markdown_minified_with(md, &MinifiedOptions::default()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"type hint "int" for "Server/Port": cannot coerce "eighty" to int"#
+    );
+
+    // The infallible parser keeps the value as written
+    let ast = parse_markdown_minified(md);
+    let server = unwrap_map(&unwrap_map(&ast)[0].1);
+    assert_eq!(unwrap_string(&server[0].1), "eighty");
+}
+
+#[test]
+fn test_minified_split_on_break() {
+    let md = r#"---
+date: 2024-01-02
+---
+
+# Entry
+First
+
+---
+date: 2024-01-05
+---
+
+# Entry
+Second
+
+***
+
+# Entry
+Third
+
+- a list with a break inside
+  ***
+"#;
+    let opts = MinifiedOptions { split_on_break: true, ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let records = unwrap_array(&ast);
+    assert_eq!(records.len(), 3);
+
+    let first = unwrap_map(&records[0]);
+    assert_eq!(first[0].0, "date");
+    assert_eq!(unwrap_string(&first[0].1), "2024-01-02");
+    assert_eq!(unwrap_string(&first[1].1), "First");
+
+    let second = unwrap_map(&records[1]);
+    assert_eq!(unwrap_string(&second[0].1), "2024-01-05");
+    assert_eq!(unwrap_string(&second[1].1), "Second");
+
+    // Only top-level breaks split, and records without front matter are just their headings
+    let third = unwrap_map(&records[2]);
+    assert_eq!(third.len(), 1);
+    assert_eq!(third[0].0, "Entry");
+    assert!(unwrap_string(&third[0].1).starts_with("Third"));
+
+    // Without the option a thematic break is ignored
+    let ast = parse_markdown_minified("# A\nx\n\n***\n\n# B\ny\n");
+    assert_eq!(unwrap_map(&ast).len(), 2);
+}
+
+#[test]
+fn test_minified_split_on_dash_breaks() {
+    // Headings and lists between `---` breaks are records, not front matter
+    let md = "## v1.0\n- fix\n\n---\n\n## v0.9\n- init\n\n---\n\n## v0.8\nfirst\n\n---\n## v0.7\n---\n\n## v0.6\n";
+    let opts = MinifiedOptions { split_on_break: true, ..Default::default() };
+    let ast = parse_markdown_minified_with(md, &opts).unwrap();
+    let records = unwrap_array(&ast);
+    assert_eq!(records.len(), 5);
+
+    let second = unwrap_map(&records[1]);
+    assert_eq!(second[0].0, "v0.9");
+    assert_eq!(unwrap_string(&unwrap_array(&second[0].1)[0]), "init");
+    assert_eq!(unwrap_map(&records[3])[0].0, "v0.7");
+}
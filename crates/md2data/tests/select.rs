@@ -0,0 +1,67 @@
+use md2data::{
+    convert_str_with, parse_markdown_minified, ConvertOptions, MinifiedNode, OutputFormat, OutputStyle, ParsingMode,
+};
+use pretty_assertions::assert_eq;
+
+const INPUT: &str = r#"
+# Services
+## API
+### Port
+8080
+
+### Hosts
+- primary
+- backup
+
+## a/b
+slash
+"#;
+
+#[test]
+fn test_select_heading_path_and_pointer() {
+    let ast = parse_markdown_minified(INPUT);
+
+    let port = MinifiedNode::String("8080".to_string());
+    assert_eq!(ast.select("Services/API/Port").unwrap(), &port);
+    assert_eq!(ast.select("/Services/API/Port").unwrap(), &port);
+
+    // Numeric segments index arrays, `~1` escapes a slash in a pointer
+    assert_eq!(ast.select("Services/API/Hosts/1").unwrap(), &MinifiedNode::String("backup".to_string()));
+    assert_eq!(ast.select("/Services/a~1b").unwrap(), &MinifiedNode::String("slash".to_string()));
+
+    // An empty path is the whole document
+    assert_eq!(ast.select("").unwrap(), &ast);
+}
+
+#[test]
+fn test_select_names_missing_segment() {
+    let ast = parse_markdown_minified(INPUT);
+
+    let err = ast.select("Services/Web/Port").unwrap_err();
+    assert_eq!(err.to_string(), r#"path "Services/Web/Port" not found: "Services" has no "Web""#);
+
+    let err = ast.select("/Nope").unwrap_err();
+    assert_eq!(err.to_string(), r#"path "/Nope" not found: the document has no "Nope""#);
+}
+
+#[test]
+fn test_select_in_conversion() {
+    let opts = ConvertOptions {
+        select: Some("Services/API".to_string()),
+        style: OutputStyle { compact: true, ..Default::default() },
+        ..Default::default()
+    };
+    let out = convert_str_with(INPUT, OutputFormat::Json, ParsingMode::Minified, &opts).unwrap();
+    assert_eq!(out, r#"{"Port":"8080","Hosts":["primary","backup"]}"#);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_select_scalar_as_toml() {
+    let opts = ConvertOptions { select: Some("Services/API/Port".to_string()), ..Default::default() };
+    let err = convert_str_with(INPUT, OutputFormat::Toml, ParsingMode::Minified, &opts).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"serialization: TOML needs a table at the top, "Services/API/Port" selects a single value"#
+    );
+}